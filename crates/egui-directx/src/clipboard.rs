@@ -0,0 +1,50 @@
+use anyhow::{bail, Result};
+use windows::Win32::{
+    Foundation::{HANDLE, HWND},
+    System::{
+        DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData},
+        Memory::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+        SystemServices::CF_UNICODETEXT,
+    },
+};
+
+/// Replaces the contents of the clipboard with `text`.
+pub fn set_clipboard_text(hwnd: HWND, text: &str) -> Result<()> {
+    let text: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+
+    unsafe {
+        if !OpenClipboard(hwnd).as_bool() {
+            bail!("failed to open the clipboard");
+        }
+        let result = set_data(&text);
+        CloseClipboard();
+        result
+    }
+}
+
+unsafe fn set_data(text: &[u16]) -> Result<()> {
+    if !EmptyClipboard().as_bool() {
+        bail!("failed to empty the clipboard");
+    }
+
+    let size = std::mem::size_of_val(text);
+    let memory = GlobalAlloc(GMEM_MOVEABLE, size);
+    if memory == 0 {
+        bail!("failed to allocate {} bytes for the clipboard", size);
+    }
+
+    let data = GlobalLock(memory) as *mut u16;
+    if data.is_null() {
+        GlobalFree(memory);
+        bail!("failed to lock the clipboard memory");
+    }
+    std::ptr::copy_nonoverlapping(text.as_ptr(), data, text.len());
+    GlobalUnlock(memory);
+
+    // The clipboard owns the memory once it has been set.
+    if SetClipboardData(CF_UNICODETEXT.0, HANDLE(memory)).is_invalid() {
+        GlobalFree(memory);
+        bail!("failed to set the clipboard data");
+    }
+    Ok(())
+}
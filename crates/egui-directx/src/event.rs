@@ -91,3 +91,19 @@ pub fn to_key(vkey: VIRTUAL_KEY) -> Option<egui::Key> {
         _ => None,
     }
 }
+
+/// A name for `vkey` that can be shown to and typed by users, like `A`, `Num5` or `F5`.
+/// Keys known to egui use the name of their `egui::Key`.
+pub fn key_name(vkey: VIRTUAL_KEY) -> Option<String> {
+    match vkey.0 {
+        code @ 0x70..=0x87 => Some(format!("F{}", code - 0x70 + 1)),
+        _ => to_key(vkey).map(|key| format!("{:?}", key)),
+    }
+}
+
+/// The first virtual key named `name` by [`key_name`], ignoring case.
+pub fn key_from_name(name: &str) -> Option<VIRTUAL_KEY> {
+    (0..=u8::MAX as u16)
+        .map(VIRTUAL_KEY)
+        .find(|&vkey| key_name(vkey).is_some_and(|n| n.eq_ignore_ascii_case(name)))
+}
@@ -1,6 +1,6 @@
 use std::time::Instant;
 
-use egui::{pos2, vec2, Event, PointerButton, Pos2, RawInput, Rect};
+use egui::{pos2, vec2, Event, Output, PointerButton, Pos2, RawInput, Rect};
 use windows::Win32::{
     Foundation::{HWND, LPARAM, RECT, WPARAM},
     UI::{
@@ -16,7 +16,7 @@ use windows::Win32::{
     },
 };
 
-use crate::{event, win32::Win32};
+use crate::{clipboard, event, win32::Win32};
 
 pub struct WindowInput {
     hwnd: HWND,
@@ -79,6 +79,14 @@ impl WindowInput {
         self.raw.take()
     }
 
+    /// Applies the parts of egui's output that affect the window, such as copied text.
+    pub fn handle_output(&self, output: &Output) -> anyhow::Result<()> {
+        if !output.copied_text.is_empty() {
+            clipboard::set_clipboard_text(self.hwnd, &output.copied_text)?;
+        }
+        Ok(())
+    }
+
     pub fn wnd_proc(&mut self, msg: u32, wparam: WPARAM, lparam: LPARAM) -> bool {
         match msg {
             WM_DPICHANGED => {
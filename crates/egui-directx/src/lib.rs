@@ -1,10 +1,13 @@
+mod clipboard;
 mod dx12;
 mod event;
 mod input;
 mod painter;
 mod win32;
 
+pub use clipboard::set_clipboard_text;
 pub use dx12::painter_dx12::PainterDX12;
+pub use event::{get_modifiers, key_from_name, key_name};
 pub use input::WindowInput;
 pub use painter::Painter;
 pub use win32::Win32;
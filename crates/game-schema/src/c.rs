@@ -0,0 +1,206 @@
+//! Generates a C header from a schema, with explicit padding so that every field is at
+//! its offset, for importing into Ghidra with File > Parse C Source.
+
+use std::{collections::HashSet, fmt::Write};
+
+use anyhow::{bail, Result};
+
+use super::{Primitive, Schema, Type, TypeDef};
+
+/// Returns a C header declaring every type in `schema`.
+pub fn to_c_header(schema: &Schema) -> Result<String> {
+    let mut out = String::from(
+        "/* Generated from the game type schema. Do not edit. */\n\n\
+         #pragma once\n\n\
+         #include <stdbool.h>\n\
+         #include <stdint.h>\n\n",
+    );
+
+    for external in &schema.externals {
+        match &external.c {
+            Some(c) => writeln!(out, "typedef {} {};", c, external.name)?,
+            None => writeln!(
+                out,
+                "typedef struct {0} {{ uint8_t data[0x{1:X}]; }} {0};",
+                external.name, external.size
+            )?,
+        }
+    }
+    if !schema.externals.is_empty() {
+        out.push('\n');
+    }
+
+    for def in &schema.types {
+        writeln!(out, "typedef struct {0} {0};", def.name)?;
+    }
+
+    let mut written = HashSet::new();
+    for def in &schema.types {
+        write_with_dependencies(&mut out, schema, def, &mut written, &mut vec![])?;
+    }
+    Ok(out)
+}
+
+/// Writes `def` after the types it contains by value, which C needs to be complete first.
+fn write_with_dependencies<'a>(
+    out: &mut String,
+    schema: &'a Schema,
+    def: &'a TypeDef,
+    written: &mut HashSet<&'a str>,
+    stack: &mut Vec<&'a str>,
+) -> Result<()> {
+    if written.contains(def.name.as_str()) {
+        return Ok(());
+    }
+    if stack.contains(&def.name.as_str()) {
+        bail!("`{}` contains itself", def.name);
+    }
+
+    stack.push(&def.name);
+    for field in &def.fields {
+        if let Some(dependency) = by_value(&field.ty).and_then(|name| schema.type_def(name)) {
+            write_with_dependencies(out, schema, dependency, written, stack)?;
+        }
+    }
+    stack.pop();
+
+    write_struct(out, schema, def)?;
+    written.insert(&def.name);
+    Ok(())
+}
+
+/// Returns the name of the type that `ty` contains by value, if any.
+fn by_value(ty: &Type) -> Option<&str> {
+    match ty {
+        Type::Named(name) => Some(name),
+        Type::Array(inner, _) => by_value(inner),
+        _ => None,
+    }
+}
+
+fn write_struct(out: &mut String, schema: &Schema, def: &TypeDef) -> Result<()> {
+    out.push('\n');
+    if !def.vtable.is_empty() {
+        write_vtable(out, def)?;
+    }
+    if let Some(doc) = &def.doc {
+        writeln!(out, "/* {} */", doc.replace("*/", "* /"))?;
+    }
+    writeln!(out, "struct {} {{", def.name)?;
+
+    let mut end = 0;
+    for (index, field) in def.fields.iter().enumerate() {
+        if field.offset < end {
+            bail!(
+                "`{}::{}` at 0x{:X} overlaps the field before it, which ends at 0x{:X}",
+                def.name,
+                field.name,
+                field.offset,
+                end
+            );
+        }
+        if field.offset > end {
+            writeln!(
+                out,
+                "    uint8_t _pad{}[0x{:X}];",
+                index,
+                field.offset - end
+            )?;
+        }
+        writeln!(
+            out,
+            "    {}; /* 0x{:X} */",
+            declare(&field.ty, field.name.clone()),
+            field.offset
+        )?;
+        end = field.offset + schema.size_of(&field.ty)?;
+    }
+
+    let size = schema.size_of_def(def)?;
+    if size < end {
+        bail!(
+            "`{}` is 0x{:X} bytes, but its fields end at 0x{:X}",
+            def.name,
+            size,
+            end
+        );
+    }
+    if size > end {
+        writeln!(out, "    uint8_t _pad_end[0x{:X}];", size - end)?;
+    }
+    writeln!(out, "}};")?;
+    Ok(())
+}
+
+/// Writes a struct of function pointers for the slots in the vtable of `def`.
+fn write_vtable(out: &mut String, def: &TypeDef) -> Result<()> {
+    let slots = def.vtable.iter().map(|slot| slot.slot).max().unwrap_or(0) + 1;
+    writeln!(out, "struct {}Vtbl {{", def.name)?;
+    for slot in 0..slots {
+        match def.vtable.iter().find(|s| s.slot == slot) {
+            Some(named) => writeln!(out, "    void* {}; /* {} */", named.name, slot)?,
+            None => writeln!(out, "    void* _slot{};", slot)?,
+        }
+    }
+    writeln!(out, "}};\n")?;
+    Ok(())
+}
+
+fn primitive_name(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::U8 => "uint8_t",
+        Primitive::I8 => "int8_t",
+        Primitive::U16 => "uint16_t",
+        Primitive::I16 => "int16_t",
+        Primitive::U32 => "uint32_t",
+        Primitive::I32 => "int32_t",
+        Primitive::U64 => "uint64_t",
+        Primitive::I64 => "int64_t",
+        Primitive::F32 => "float",
+        Primitive::F64 => "double",
+        Primitive::Bool => "bool",
+    }
+}
+
+/// Returns a C declaration of `declarator` as `ty`, such as `Queue queues[4]`.
+fn declare(ty: &Type, declarator: String) -> String {
+    match ty {
+        Type::Primitive(primitive) => format!("{} {}", primitive_name(*primitive), declarator),
+        Type::Void => format!("void {}", declarator),
+        Type::Named(name) => format!("{} {}", name, declarator),
+        Type::Pointer(inner) => match **inner {
+            Type::Array(..) => declare(inner, format!("(*{})", declarator)),
+            _ => declare(inner, format!("*{}", declarator)),
+        },
+        Type::Array(inner, len) => declare(inner, format!("{}[{}]", declarator, len)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SCHEMA;
+
+    #[test]
+    fn pads_fields_to_their_offsets() {
+        let header = to_c_header(&Schema::parse(SCHEMA).unwrap()).unwrap();
+        assert!(header.contains("typedef void* HANDLE;"));
+        assert!(header.contains("typedef struct LOCK { uint8_t data[0x28]; } LOCK;"));
+        assert!(header.contains(
+            "struct Device {\n    uint8_t _pad0[0x10];\n    void *swap_chain; /* 0x10 */\n    \
+             uint8_t _pad1[0x8];\n    Queue queues[4]; /* 0x20 */\n};"
+        ));
+        assert!(header.contains("    Device *device; /* 0xF8 */\n};"));
+        assert!(header.contains("struct DeviceVtbl {\n    void* _slot0;\n    void* _slot1;\n    void* Present; /* 2 */\n};"));
+    }
+
+    #[test]
+    fn writes_contained_types_first() {
+        let schema = r#"{ "types": [
+            { "name": "Outer", "fields": [{ "name": "inner", "offset": 0, "type": "Inner[2]" }] },
+            { "name": "Inner", "fields": [{ "name": "value", "offset": 0, "type": "u32" }] }
+        ] }"#;
+        let header = to_c_header(&Schema::parse(schema).unwrap()).unwrap();
+        assert!(header.find("struct Inner {").unwrap() < header.find("struct Outer {").unwrap());
+    }
+}
@@ -0,0 +1,519 @@
+//! Describes the game's types in a JSON file that is shared with reverse engineering
+//! tools, and generates code from it: Rust types for the payload and a C header that
+//! Ghidra can parse.
+//!
+//! A schema looks like this:
+//!
+//! ```json
+//! {
+//!     "externals": {
+//!         "HANDLE": { "rust": "::windows::Win32::Foundation::HANDLE", "size": 8, "c": "void*" }
+//!     },
+//!     "types": [
+//!         {
+//!             "name": "ZRenderManager",
+//!             "doc": "Owns the render device.",
+//!             "size": "0x14180",
+//!             "vtable": [{ "slot": 0, "name": "Destructor" }],
+//!             "fields": [
+//!                 { "name": "device", "offset": "0x14178", "type": "ZRenderDevice*" }
+//!             ]
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! Field types are the primitives `u8` to `u64`, `i8` to `i64`, `f32`, `f64` and `bool`,
+//! the names of other types or externals, `void*`, and pointers (`T*`) and arrays (`T[4]`)
+//! of those. Externals are types defined elsewhere, such as by the Windows API, which are
+//! used as they are in Rust and as opaque bytes in C unless given a C type. Numbers can be
+//! written as JSON numbers or as strings, so that offsets can be in hexadecimal.
+
+mod c;
+mod rust;
+
+use std::{collections::HashSet, fmt, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+
+pub use c::to_c_header;
+pub use rust::to_rust;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Primitive {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    Bool,
+}
+
+impl Primitive {
+    const ALL: [Primitive; 11] = [
+        Primitive::U8,
+        Primitive::I8,
+        Primitive::U16,
+        Primitive::I16,
+        Primitive::U32,
+        Primitive::I32,
+        Primitive::U64,
+        Primitive::I64,
+        Primitive::F32,
+        Primitive::F64,
+        Primitive::Bool,
+    ];
+
+    /// The name used in schemas, which is also the Rust name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Primitive::U8 => "u8",
+            Primitive::I8 => "i8",
+            Primitive::U16 => "u16",
+            Primitive::I16 => "i16",
+            Primitive::U32 => "u32",
+            Primitive::I32 => "i32",
+            Primitive::U64 => "u64",
+            Primitive::I64 => "i64",
+            Primitive::F32 => "f32",
+            Primitive::F64 => "f64",
+            Primitive::Bool => "bool",
+        }
+    }
+
+    pub fn size(self) -> u64 {
+        match self {
+            Primitive::U8 | Primitive::I8 | Primitive::Bool => 1,
+            Primitive::U16 | Primitive::I16 => 2,
+            Primitive::U32 | Primitive::I32 | Primitive::F32 => 4,
+            Primitive::U64 | Primitive::I64 | Primitive::F64 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Type {
+    Primitive(Primitive),
+    /// Only valid behind a pointer.
+    Void,
+    /// A type or external defined in the schema.
+    Named(String),
+    Pointer(Box<Type>),
+    Array(Box<Type>, u64),
+}
+
+impl Type {
+    /// Parses a type written as `u32`, `ZRenderDevice*` or `ZRenderCommandQueue[4]`.
+    pub fn parse(text: &str) -> Result<Type> {
+        let text = text.trim();
+        if let Some(inner) = text.strip_suffix(']') {
+            let (element, len) = inner
+                .rsplit_once('[')
+                .with_context(|| format!("`{}` has an unmatched `]`", text))?;
+            let len = parse_number(len.trim())
+                .with_context(|| format!("`{}` has an invalid array length", text))?;
+            return Ok(Type::Array(Box::new(Type::parse(element)?), len));
+        }
+        if let Some(pointee) = text.strip_suffix('*') {
+            return Ok(Type::Pointer(Box::new(Type::parse(pointee)?)));
+        }
+
+        if text == "void" {
+            return Ok(Type::Void);
+        }
+        if let Some(primitive) = Primitive::ALL.iter().find(|p| p.name() == text) {
+            return Ok(Type::Primitive(*primitive));
+        }
+        if text.is_empty()
+            || text.starts_with(|c: char| c.is_ascii_digit())
+            || !text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            bail!("`{}` is not a type name", text);
+        }
+        Ok(Type::Named(text.to_owned()))
+    }
+
+    /// Calls `f` with the name of every type this refers to.
+    fn visit_names<'a>(&'a self, f: &mut impl FnMut(&'a str)) {
+        match self {
+            Type::Primitive(_) | Type::Void => {}
+            Type::Named(name) => f(name),
+            Type::Pointer(inner) | Type::Array(inner, _) => inner.visit_names(f),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Primitive(primitive) => f.write_str(primitive.name()),
+            Type::Void => f.write_str("void"),
+            Type::Named(name) => f.write_str(name),
+            Type::Pointer(inner) => write!(f, "{}*", inner),
+            Type::Array(inner, len) => write!(f, "{}[{}]", inner, len),
+        }
+    }
+}
+
+/// A type defined outside the schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct External {
+    pub name: String,
+    /// The path of the type in Rust.
+    pub rust: String,
+    pub size: u64,
+    /// The type in C, if it is not to be opaque bytes.
+    pub c: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub offset: u64,
+    pub ty: Type,
+    pub doc: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VtableSlot {
+    pub slot: u64,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeDef {
+    pub name: String,
+    /// The size of the type, if it is larger than its last field.
+    pub size: Option<u64>,
+    pub doc: Option<String>,
+    pub fields: Vec<Field>,
+    pub vtable: Vec<VtableSlot>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    pub externals: Vec<External>,
+    pub types: Vec<TypeDef>,
+}
+
+impl Schema {
+    pub fn load(path: impl AsRef<Path>) -> Result<Schema> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Schema::parse(&text).with_context(|| format!("Invalid schema in {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Schema> {
+        let root: Value = serde_json::from_str(text)?;
+        let root = object(&root, "the schema")?;
+
+        let mut schema = Schema::default();
+        if let Some(externals) = root.get("externals") {
+            for (name, external) in object(externals, "`externals`")? {
+                schema.externals.push(parse_external(name, external)?);
+            }
+        }
+        if let Some(types) = root.get("types") {
+            let types = types.as_array().context("`types` must be an array")?;
+            for ty in types {
+                schema.types.push(parse_type_def(ty)?);
+            }
+        }
+
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    pub fn type_def(&self, name: &str) -> Option<&TypeDef> {
+        self.types.iter().find(|ty| ty.name == name)
+    }
+
+    pub fn external(&self, name: &str) -> Option<&External> {
+        self.externals.iter().find(|external| external.name == name)
+    }
+
+    /// Returns the size of `ty` in bytes.
+    pub fn size_of(&self, ty: &Type) -> Result<u64> {
+        Ok(match ty {
+            Type::Primitive(primitive) => primitive.size(),
+            Type::Void => bail!("`void` has no size"),
+            Type::Pointer(_) => 8,
+            Type::Array(inner, len) => self.size_of(inner)? * len,
+            Type::Named(name) => match (self.type_def(name), self.external(name)) {
+                (Some(def), _) => self.size_of_def(def)?,
+                (None, Some(external)) => external.size,
+                (None, None) => bail!("Unknown type `{}`", name),
+            },
+        })
+    }
+
+    /// Returns the size of `def`, which is where its last field ends if it has no size.
+    pub fn size_of_def(&self, def: &TypeDef) -> Result<u64> {
+        if let Some(size) = def.size {
+            return Ok(size);
+        }
+        match def.fields.last() {
+            Some(field) => Ok(field.offset + self.size_of(&field.ty)?),
+            None => Ok(0),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        let all_names = self
+            .externals
+            .iter()
+            .map(|external| &external.name)
+            .chain(self.types.iter().map(|def| &def.name));
+        for name in all_names {
+            if !names.insert(name.as_str()) {
+                bail!("`{}` is defined more than once", name);
+            }
+        }
+
+        for def in &self.types {
+            let mut fields = HashSet::new();
+            for field in &def.fields {
+                if !fields.insert(field.name.as_str()) {
+                    bail!(
+                        "`{}` has more than one field called `{}`",
+                        def.name,
+                        field.name
+                    );
+                }
+                if field.ty == Type::Void {
+                    bail!("`{}::{}` cannot be `void`", def.name, field.name);
+                }
+
+                let mut unknown = None;
+                field.ty.visit_names(&mut |name| {
+                    if !names.contains(name) {
+                        unknown = Some(name);
+                    }
+                });
+                if let Some(name) = unknown {
+                    bail!(
+                        "`{}::{}` refers to unknown type `{}`",
+                        def.name,
+                        field.name,
+                        name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a number written as a JSON number, or as a decimal or `0x`-prefixed
+/// hexadecimal string.
+fn parse_number(text: &str) -> Result<u64> {
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(digits) => u64::from_str_radix(digits, 16),
+        None => text.parse(),
+    };
+    value.with_context(|| format!("`{}` is not a number", text))
+}
+
+fn number(value: &Value, what: &str) -> Result<u64> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .with_context(|| format!("{} must be a whole number", what)),
+        Value::String(text) => parse_number(text).with_context(|| format!("Invalid {}", what)),
+        _ => bail!("{} must be a number", what),
+    }
+}
+
+fn object<'a>(value: &'a Value, what: &str) -> Result<&'a Map<String, Value>> {
+    value
+        .as_object()
+        .with_context(|| format!("{} must be an object", what))
+}
+
+fn string(object: &Map<String, Value>, key: &str, what: &str) -> Result<String> {
+    object
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .with_context(|| format!("{} needs a `{}` string", what, key))
+}
+
+fn optional_string(object: &Map<String, Value>, key: &str) -> Option<String> {
+    object.get(key).and_then(Value::as_str).map(str::to_owned)
+}
+
+fn parse_external(name: &str, value: &Value) -> Result<External> {
+    let what = format!("External `{}`", name);
+    let external = object(value, &what)?;
+    Ok(External {
+        name: name.to_owned(),
+        rust: string(external, "rust", &what)?,
+        size: number(
+            external
+                .get("size")
+                .with_context(|| format!("{} needs a `size`", what))?,
+            &format!("size of `{}`", name),
+        )?,
+        c: optional_string(external, "c"),
+    })
+}
+
+fn parse_type_def(value: &Value) -> Result<TypeDef> {
+    let def = object(value, "Each type")?;
+    let name = string(def, "name", "Each type")?;
+
+    let size = def
+        .get("size")
+        .map(|size| number(size, &format!("size of `{}`", name)))
+        .transpose()?;
+
+    let mut fields = vec![];
+    if let Some(values) = def.get("fields") {
+        let values = values
+            .as_array()
+            .with_context(|| format!("The fields of `{}` must be an array", name))?;
+        for value in values {
+            let what = format!("Each field of `{}`", name);
+            let field = object(value, &what)?;
+            let field_name = string(field, "name", &what)?;
+            let what = format!("`{}::{}`", name, field_name);
+            fields.push(Field {
+                offset: number(
+                    field
+                        .get("offset")
+                        .with_context(|| format!("{} needs an `offset`", what))?,
+                    &format!("offset of {}", what),
+                )?,
+                ty: Type::parse(&string(field, "type", &what)?)
+                    .with_context(|| format!("Invalid type for {}", what))?,
+                doc: optional_string(field, "doc"),
+                name: field_name,
+            });
+        }
+    }
+
+    let mut vtable = vec![];
+    if let Some(values) = def.get("vtable") {
+        let values = values
+            .as_array()
+            .with_context(|| format!("The vtable of `{}` must be an array", name))?;
+        for value in values {
+            let what = format!("Each vtable slot of `{}`", name);
+            let slot = object(value, &what)?;
+            vtable.push(VtableSlot {
+                slot: number(
+                    slot.get("slot")
+                        .with_context(|| format!("{} needs a `slot`", what))?,
+                    &what,
+                )?,
+                name: string(slot, "name", &what)?,
+            });
+        }
+    }
+
+    Ok(TypeDef {
+        doc: optional_string(def, "doc"),
+        name,
+        size,
+        fields,
+        vtable,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) const SCHEMA: &str = r#"{
+        "externals": {
+            "HANDLE": { "rust": "::windows::Win32::Foundation::HANDLE", "size": 8, "c": "void*" },
+            "LOCK": { "rust": "crate::Lock", "size": "0x28" }
+        },
+        "types": [
+            {
+                "name": "Queue",
+                "fields": [
+                    { "name": "lock", "offset": 0, "type": "LOCK" },
+                    { "name": "event", "offset": "0x28", "type": "HANDLE", "doc": "Signalled when done." }
+                ]
+            },
+            {
+                "name": "Device",
+                "doc": "The render device.",
+                "vtable": [{ "slot": 2, "name": "Present" }],
+                "fields": [
+                    { "name": "swap_chain", "offset": "0x10", "type": "void*" },
+                    { "name": "queues", "offset": "0x20", "type": "Queue[4]" }
+                ]
+            },
+            {
+                "name": "Manager",
+                "size": "0x100",
+                "fields": [{ "name": "device", "offset": "0xF8", "type": "Device*" }]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn parses_types() {
+        assert_eq!(Type::parse("u32").unwrap(), Type::Primitive(Primitive::U32));
+        assert_eq!(
+            Type::parse("Device*[0x2]").unwrap(),
+            Type::Array(
+                Box::new(Type::Pointer(Box::new(Type::Named("Device".into())))),
+                2
+            )
+        );
+        assert_eq!(Type::parse("void *").unwrap().to_string(), "void*");
+        assert!(Type::parse("2Fast").is_err());
+        assert!(Type::parse("u8[").is_err());
+    }
+
+    #[test]
+    fn parses_schema() {
+        let schema = Schema::parse(SCHEMA).unwrap();
+        assert_eq!(schema.externals.len(), 2);
+        assert_eq!(schema.external("LOCK").unwrap().size, 0x28);
+
+        let device = schema.type_def("Device").unwrap();
+        assert_eq!(device.doc.as_deref(), Some("The render device."));
+        assert_eq!(device.fields[1].offset, 0x20);
+        assert_eq!(device.vtable[0].name, "Present");
+
+        assert_eq!(
+            schema
+                .size_of_def(schema.type_def("Queue").unwrap())
+                .unwrap(),
+            0x30
+        );
+        assert_eq!(schema.size_of_def(device).unwrap(), 0x20 + 4 * 0x30);
+        assert_eq!(
+            schema
+                .size_of_def(schema.type_def("Manager").unwrap())
+                .unwrap(),
+            0x100
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_duplicate_types() {
+        let unknown = r#"{ "types": [{ "name": "A", "fields": [
+            { "name": "b", "offset": 0, "type": "B*" }
+        ] }] }"#;
+        assert!(Schema::parse(unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown type `B`"));
+
+        let duplicate = r#"{ "types": [{ "name": "A" }, { "name": "A" }] }"#;
+        assert!(Schema::parse(duplicate).is_err());
+    }
+}
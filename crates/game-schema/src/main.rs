@@ -0,0 +1,25 @@
+use anyhow::{bail, Result};
+
+use game_schema::Schema;
+
+const USAGE: &str = "Usage: game-schema <header|rust> <schema.json>
+
+Prints a C header for Ghidra, or the Rust code the payload builds, generated from a
+game type schema.";
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (format, path) = match &args[..] {
+        [format, path] => (format.as_str(), path),
+        _ => bail!("{}", USAGE),
+    };
+
+    let schema = Schema::load(path)?;
+    let output = match format {
+        "header" => game_schema::to_c_header(&schema)?,
+        "rust" => game_schema::to_rust(&schema)?,
+        _ => bail!("Unknown output `{}`.\n\n{}", format, USAGE),
+    };
+    print!("{}", output);
+    Ok(())
+}
@@ -0,0 +1,190 @@
+//! Generates Rust types from a schema, laid out with `#[game_struct]`, and a table
+//! describing them for inspecting them at runtime.
+//!
+//! The generated code expects `game_struct` and the reflection types `TypeInfo`,
+//! `FieldInfo`, `VtableSlot`, `TypeRef` and `Primitive` to be in scope where it is
+//! included.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use super::{Field, Schema, Type, TypeDef};
+
+/// Returns Rust code defining every type in `schema`, and a `TYPES` table describing them.
+pub fn to_rust(schema: &Schema) -> Result<String> {
+    let mut out = String::from("// Generated from the game type schema. Do not edit.\n");
+    for def in &schema.types {
+        out.push('\n');
+        write_struct(&mut out, schema, def)?;
+        write_accessors(&mut out, schema, def)?;
+    }
+    out.push('\n');
+    write_table(&mut out, schema)?;
+    Ok(out)
+}
+
+fn write_doc(out: &mut String, indent: &str, doc: &Option<String>) -> Result<()> {
+    for line in doc.iter().flat_map(|doc| doc.lines()) {
+        writeln!(out, "{}/// {}", indent, line)?;
+    }
+    Ok(())
+}
+
+fn write_struct(out: &mut String, schema: &Schema, def: &TypeDef) -> Result<()> {
+    write_doc(out, "", &def.doc)?;
+    match def.size {
+        Some(size) => writeln!(out, "#[game_struct(size = 0x{:X})]", size)?,
+        None => writeln!(out, "#[game_struct]")?,
+    }
+    writeln!(out, "pub struct {} {{", def.name)?;
+    for field in &def.fields {
+        write_doc(out, "    ", &field.doc)?;
+        writeln!(out, "    #[offset(0x{:X})]", field.offset)?;
+        writeln!(
+            out,
+            "    pub {}: {},",
+            field.name,
+            rust_type(schema, &field.ty)
+        )?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Writes a method for each pointer field that returns what it points to.
+fn write_accessors(out: &mut String, schema: &Schema, def: &TypeDef) -> Result<()> {
+    let pointers: Vec<(&Field, &Type)> = def
+        .fields
+        .iter()
+        .filter_map(|field| match &field.ty {
+            Type::Pointer(pointee) if **pointee != Type::Void => Some((field, &**pointee)),
+            _ => None,
+        })
+        .collect();
+    if pointers.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\nimpl {} {{", def.name)?;
+    for (index, (field, pointee)) in pointers.into_iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let pointee = rust_type(schema, pointee);
+        writeln!(
+            out,
+            "    /// Returns what `{}` points to, or `None` if it is null.",
+            field.name
+        )?;
+        writeln!(out, "    ///")?;
+        writeln!(out, "    /// # Safety")?;
+        writeln!(
+            out,
+            "    /// `{}` must be null or point to a valid `{}`.",
+            field.name, pointee
+        )?;
+        writeln!(
+            out,
+            "    pub unsafe fn {}(&self) -> Option<&{}> {{",
+            field.name, pointee
+        )?;
+        writeln!(out, "        self.{}.as_ref()", field.name)?;
+        writeln!(out, "    }}")?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+fn write_table(out: &mut String, schema: &Schema) -> Result<()> {
+    writeln!(
+        out,
+        "/// Every type in the schema, for inspecting them at runtime."
+    )?;
+    writeln!(out, "pub static TYPES: &[TypeInfo] = &[")?;
+    for def in &schema.types {
+        writeln!(out, "    TypeInfo {{")?;
+        writeln!(out, "        name: {:?},", def.name)?;
+        writeln!(out, "        doc: {:?},", def.doc)?;
+        writeln!(out, "        size: ::core::mem::size_of::<{}>(),", def.name)?;
+        writeln!(out, "        fields: &[")?;
+        for field in &def.fields {
+            writeln!(out, "            FieldInfo {{")?;
+            writeln!(out, "                name: {:?},", field.name)?;
+            writeln!(out, "                offset: 0x{:X},", field.offset)?;
+            writeln!(out, "                ty: {},", type_ref(schema, &field.ty))?;
+            writeln!(out, "                doc: {:?},", field.doc)?;
+            writeln!(out, "            }},")?;
+        }
+        writeln!(out, "        ],")?;
+        writeln!(out, "        vtable: &[")?;
+        for slot in &def.vtable {
+            writeln!(
+                out,
+                "            VtableSlot {{ slot: {}, name: {:?} }},",
+                slot.slot, slot.name
+            )?;
+        }
+        writeln!(out, "        ],")?;
+        writeln!(out, "    }},")?;
+    }
+    writeln!(out, "];")?;
+    Ok(())
+}
+
+fn rust_type(schema: &Schema, ty: &Type) -> String {
+    match ty {
+        Type::Primitive(primitive) => primitive.name().to_owned(),
+        Type::Void => "::core::ffi::c_void".to_owned(),
+        Type::Named(name) => match schema.external(name) {
+            Some(external) => external.rust.clone(),
+            None => name.clone(),
+        },
+        Type::Pointer(inner) => format!("*const {}", rust_type(schema, inner)),
+        Type::Array(inner, len) => format!("[{}; {}]", rust_type(schema, inner), len),
+    }
+}
+
+/// Returns an expression for the `TypeRef` describing `ty`.
+fn type_ref(schema: &Schema, ty: &Type) -> String {
+    match ty {
+        Type::Primitive(primitive) => format!("TypeRef::Primitive(Primitive::{:?})", primitive),
+        Type::Void => "TypeRef::Void".to_owned(),
+        Type::Named(name) => match schema.external(name) {
+            Some(external) => format!(
+                "TypeRef::Opaque {{ name: {:?}, size: {} }}",
+                name, external.size
+            ),
+            None => format!("TypeRef::Struct({:?})", name),
+        },
+        Type::Pointer(inner) => format!("TypeRef::Pointer(&{})", type_ref(schema, inner)),
+        Type::Array(inner, len) => format!("TypeRef::Array(&{}, {})", type_ref(schema, inner), len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::SCHEMA;
+
+    #[test]
+    fn generates_structs_and_accessors() {
+        let rust = to_rust(&Schema::parse(SCHEMA).unwrap()).unwrap();
+        assert!(rust.contains(
+            "/// The render device.\n#[game_struct]\npub struct Device {\n    #[offset(0x10)]\n    \
+             pub swap_chain: *const ::core::ffi::c_void,\n    #[offset(0x20)]\n    pub queues: [Queue; 4],\n}"
+        ));
+        assert!(rust.contains("#[game_struct(size = 0x100)]\npub struct Manager {"));
+        assert!(rust.contains("pub unsafe fn device(&self) -> Option<&Device> {"));
+        assert!(!rust.contains("pub unsafe fn swap_chain"));
+    }
+
+    #[test]
+    fn generates_reflection_table() {
+        let rust = to_rust(&Schema::parse(SCHEMA).unwrap()).unwrap();
+        assert!(rust.contains("ty: TypeRef::Opaque { name: \"LOCK\", size: 40 },"));
+        assert!(rust.contains("ty: TypeRef::Array(&TypeRef::Struct(\"Queue\"), 4),"));
+        assert!(rust.contains("ty: TypeRef::Pointer(&TypeRef::Struct(\"Device\")),"));
+        assert!(rust.contains("VtableSlot { slot: 2, name: \"Present\" },"));
+    }
+}
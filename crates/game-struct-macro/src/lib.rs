@@ -0,0 +1,167 @@
+//! Lays out game structures from the offsets of their fields, instead of from hand-sized
+//! padding that silently drifts when a field is added or resized.
+//!
+//! ```
+//! use game_struct_macro::game_struct;
+//!
+//! #[game_struct(size = 0x20)]
+//! pub struct Entity {
+//!     #[offset(0x8)]
+//!     pub id: u32,
+//!     #[offset(0x10)]
+//!     pub position: [f32; 3],
+//! }
+//!
+//! assert_eq!(std::mem::offset_of!(Entity, position), 0x10);
+//! assert_eq!(std::mem::size_of::<Entity>(), 0x20);
+//! ```
+//!
+//! The struct is made `#[repr(C)]`, with private padding before each field, and the
+//! offsets and size are checked when it is compiled, so a field that no longer fits is a
+//! build error rather than a misread at runtime:
+//!
+//! ```compile_fail
+//! use game_struct_macro::game_struct;
+//!
+//! #[game_struct]
+//! pub struct Entity {
+//!     #[offset(0x0)]
+//!     pub id: u64,
+//!     #[offset(0x4)]
+//!     pub flags: u32,
+//! }
+//! ```
+//!
+//! So is a field that alignment moves away from its offset:
+//!
+//! ```compile_fail
+//! use game_struct_macro::game_struct;
+//!
+//! #[game_struct]
+//! pub struct Entity {
+//!     #[offset(0x2)]
+//!     pub id: u64,
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{parse_macro_input, spanned::Spanned, Fields, ItemStruct, LitInt};
+
+/// Lays out a struct whose fields are each marked with `#[offset(...)]`, optionally
+/// padding it to `size`. See the crate documentation for an example.
+#[proc_macro_attribute]
+pub fn game_struct(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut size = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("size") {
+            size = Some(meta.value()?.parse::<LitInt>()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `size = ...`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+    let item = parse_macro_input!(item as ItemStruct);
+
+    expand(item, size)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Field {
+    offset: LitInt,
+    field: syn::Field,
+}
+
+fn expand(mut item: ItemStruct, size: Option<LitInt>) -> syn::Result<TokenStream2> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item.generics.span(),
+            "game structs cannot be generic",
+        ));
+    }
+    if let Some(repr) = item.attrs.iter().find(|attr| attr.path().is_ident("repr")) {
+        return Err(syn::Error::new(
+            repr.span(),
+            "game structs are made `#[repr(C)]` by `#[game_struct]`",
+        ));
+    }
+
+    let fields = match &mut item.fields {
+        Fields::Named(fields) => std::mem::take(&mut fields.named),
+        fields => {
+            return Err(syn::Error::new(
+                fields.span(),
+                "game structs must have named fields",
+            ))
+        }
+    };
+    let fields = fields
+        .into_iter()
+        .map(take_offset)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    for pair in fields.windows(2) {
+        if pair[1].offset.base10_parse::<u64>()? <= pair[0].offset.base10_parse::<u64>()? {
+            return Err(syn::Error::new(
+                pair[1].offset.span(),
+                "offsets must increase from one field to the next",
+            ));
+        }
+    }
+
+    let name = &item.ident;
+    let mut laid_out = vec![];
+    let mut assertions = vec![];
+    let mut end = quote!(0);
+    for (index, Field { offset, field }) in fields.iter().enumerate() {
+        let pad = format_ident!("_pad{}", index);
+        laid_out.push(quote_spanned!(offset.span()=> #pad: [u8; #offset - (#end)],));
+        laid_out.push(quote!(#field,));
+
+        let ident = field.ident.as_ref().expect("named fields have names");
+        let ty = &field.ty;
+        let message = format!("`{}::{}` is not at offset {}", name, ident, offset);
+        assertions.push(quote! {
+            assert!(::core::mem::offset_of!(#name, #ident) == #offset, #message);
+        });
+        end = quote!(#offset + ::core::mem::size_of::<#ty>());
+    }
+
+    if let Some(size) = &size {
+        laid_out.push(quote_spanned!(size.span()=> _pad_end: [u8; #size - (#end)],));
+        let message = format!("`{}` is not {} bytes", name, size);
+        assertions.push(quote! {
+            assert!(::core::mem::size_of::<#name>() == #size, #message);
+        });
+    }
+
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let struct_token = &item.struct_token;
+    Ok(quote! {
+        #[repr(C)]
+        #(#attrs)*
+        #vis #struct_token #name {
+            #(#laid_out)*
+        }
+
+        const _: () = {
+            #(#assertions)*
+        };
+    })
+}
+
+/// Removes the `#[offset(...)]` attribute from `field`, which every field must have.
+fn take_offset(mut field: syn::Field) -> syn::Result<Field> {
+    let index = field
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("offset"))
+        .ok_or_else(|| syn::Error::new(field.span(), "missing `#[offset(...)]`"))?;
+    let offset = field.attrs.remove(index).parse_args::<LitInt>()?;
+    offset.base10_parse::<u64>()?;
+    Ok(Field { offset, field })
+}
@@ -0,0 +1,54 @@
+use std::mem::{offset_of, size_of};
+
+use game_struct_macro::game_struct;
+
+#[game_struct]
+struct Packed {
+    #[offset(0x0)]
+    a: u32,
+    #[offset(0x4)]
+    b: u32,
+    #[offset(0x8)]
+    c: u64,
+}
+
+#[game_struct(size = 0x14180)]
+struct Manager {
+    /// Doc comments and other attributes are kept.
+    #[offset(0x14178)]
+    device: *const Packed,
+}
+
+#[game_struct]
+struct Nested {
+    #[offset(0x10)]
+    inner: [Packed; 4],
+    #[offset(0x60)]
+    flag: bool,
+}
+
+#[test]
+fn fields_are_at_their_offsets() {
+    assert_eq!(offset_of!(Packed, a), 0);
+    assert_eq!(offset_of!(Packed, b), 4);
+    assert_eq!(offset_of!(Packed, c), 8);
+    assert_eq!(size_of::<Packed>(), 0x10);
+
+    assert_eq!(offset_of!(Manager, device), 0x14178);
+    assert_eq!(offset_of!(Nested, inner), 0x10);
+    assert_eq!(offset_of!(Nested, flag), 0x60);
+}
+
+#[test]
+fn size_pads_the_end() {
+    assert_eq!(size_of::<Manager>(), 0x14180);
+}
+
+#[test]
+fn fields_can_be_read_through_pointers() {
+    let mut bytes = [0u64; 4];
+    bytes[1] = 0x1234;
+    let packed = unsafe { &*(bytes.as_ptr() as *const Packed) };
+    assert_eq!(packed.c, 0x1234);
+    assert_eq!(packed.a, 0);
+}
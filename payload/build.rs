@@ -0,0 +1,18 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::Context;
+
+/// The game types, shared with the reverse engineering tools.
+const SCHEMA: &str = "../schema/game.json";
+
+fn main() -> anyhow::Result<()> {
+    println!("cargo:rerun-if-changed={}", SCHEMA);
+
+    let schema = game_schema::Schema::load(SCHEMA)?;
+    let out_dir = PathBuf::from(env::var("OUT_DIR").context("OUT_DIR is not set")?);
+    fs::write(
+        out_dir.join("game_types.rs"),
+        game_schema::to_rust(&schema)?,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use std::{collections::BTreeMap, fmt, fs, path::PathBuf, str::FromStr, sync::Mutex};
+
+use egui_directx::{get_modifiers, key_from_name, key_name};
+use lazy_static::lazy_static;
+use windows::Win32::{
+    Foundation::{LPARAM, WPARAM},
+    UI::{
+        Input::KeyboardAndMouse::VIRTUAL_KEY,
+        WindowsAndMessaging::{WM_KEYDOWN, WM_SYSKEYDOWN},
+    },
+};
+
+use crate::console::{self, ArgType, CommandSignature, Console};
+
+pub const BINDINGS_FILENAME: &str = "bindings.cfg";
+
+/// A key along with the modifiers that must be held with it, like `ctrl+shift+F5`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeyChord {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    /// The canonical name of the key, as given by [`key_name`].
+    key: String,
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut chord = KeyChord {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            key: String::new(),
+        };
+
+        let mut parts: Vec<_> = text.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "shift" => chord.shift = true,
+                "alt" => chord.alt = true,
+                _ => anyhow::bail!("`{}` is not a modifier (ctrl, shift or alt).", modifier),
+            }
+        }
+
+        chord.key = key_from_name(key)
+            .and_then(key_name)
+            .ok_or_else(|| anyhow::anyhow!("`{}` is not a key that can be bound.", key))?;
+        Ok(chord)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "shift+")?;
+        }
+        if self.alt {
+            write!(f, "alt+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Command lines that run when a key chord is pressed in-game.
+pub struct Bindings {
+    bindings: BTreeMap<KeyChord, String>,
+    path: Option<PathBuf>,
+}
+
+impl Bindings {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            bindings: BTreeMap::new(),
+            path,
+        }
+    }
+
+    pub fn bind(&mut self, chord: KeyChord, line: String) {
+        self.bindings.insert(chord, line);
+    }
+
+    pub fn unbind(&mut self, chord: &KeyChord) -> Option<String> {
+        self.bindings.remove(chord)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&KeyChord, &String)> {
+        self.bindings.iter()
+    }
+
+    /// Returns the command line bound to the key pressed by `msg`, if any. Held keys
+    /// only trigger their binding once.
+    pub fn command_for_message(&self, msg: u32, wparam: WPARAM, lparam: LPARAM) -> Option<String> {
+        let repeated = lparam.0 & (1 << 30) != 0;
+        if !matches!(msg, WM_KEYDOWN | WM_SYSKEYDOWN) || repeated {
+            return None;
+        }
+
+        let modifiers = get_modifiers();
+        let chord = KeyChord {
+            ctrl: modifiers.ctrl,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+            key: key_name(VIRTUAL_KEY(wparam.0 as u16))?,
+        };
+        self.bindings.get(&chord).cloned()
+    }
+
+    /// Loads the bindings saved in the config file. Lines that cannot be applied are
+    /// skipped, and reported together once the rest have been loaded.
+    pub fn load(&mut self) -> anyhow::Result<()> {
+        let path = match &self.path {
+            Some(path) if path.exists() => path.clone(),
+            _ => return Ok(()),
+        };
+
+        let contents = fs::read_to_string(&path)?;
+        let mut errors = vec![];
+        for (index, line) in contents.lines().enumerate() {
+            if let Err(e) = self.load_line(line) {
+                errors.push(format!("{}:{}: {}", path.display(), index + 1, e));
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => anyhow::bail!("{}", errors.join("\n")),
+        }
+    }
+
+    fn load_line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+            return Ok(());
+        }
+
+        let mut errors = vec![];
+        for words in console::tokenize(line).map_err(|e| e.to_string())? {
+            let result = match &words[..] {
+                [bind, chord, line] if bind == "bind" => chord
+                    .parse()
+                    .map(|chord| self.bind(chord, line.clone()))
+                    .map_err(|e: anyhow::Error| e.to_string()),
+                _ => Err("expected `bind <key> <command line>`".to_owned()),
+            };
+            errors.extend(result.err());
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join("; ")),
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            let contents: String = self
+                .bindings
+                .iter()
+                .map(|(chord, line)| format!("bind {} {}\n", chord, console::quote(line)))
+                .collect();
+            fs::write(path, contents)?;
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    pub static ref BINDINGS: Mutex<Bindings> = Mutex::new(Bindings::new(
+        crate::paths::payload_file(BINDINGS_FILENAME).ok()
+    ));
+}
+
+fn save_after_change(console: &mut Console, bindings: &Bindings) {
+    if let Err(err) = bindings.save() {
+        console.push_back_error(format!("Failed to save key bindings: {}", err));
+    }
+}
+
+pub fn register_commands(console: &mut Console) {
+    if let Err(err) = BINDINGS.lock().unwrap().load() {
+        console.push_back_error(format!("Failed to load key bindings: {}", err));
+    }
+
+    console.register_command(
+        CommandSignature::new("bind", "Runs a command line when a key is pressed in-game.")
+            .optional_arg(
+                "key",
+                ArgType::String,
+                "",
+                "A key like `F5` or `ctrl+shift+K`.",
+            )
+            .rest("line", ArgType::String, "The command line to run."),
+        |console, args| {
+            let key = args.string("key")?;
+            let line = args.rest_line();
+
+            let mut bindings = BINDINGS.lock().unwrap();
+            if key.is_empty() || line.is_empty() {
+                let chord = match key {
+                    "" => None,
+                    key => Some(key.parse::<KeyChord>()?),
+                };
+                let lines: Vec<_> = bindings
+                    .iter()
+                    .filter(|(c, _)| chord.as_ref().is_none_or(|chord| chord == *c))
+                    .map(|(chord, line)| format!("{} = {}", chord, line))
+                    .collect();
+                for line in lines {
+                    console.push_back_info(line);
+                }
+                return Ok(());
+            }
+
+            console::tokenize(&line)?;
+            bindings.bind(key.parse()?, line);
+            save_after_change(console, &bindings);
+            Ok(())
+        },
+    );
+
+    console.register_command_with_completer(
+        CommandSignature::new("unbind", "Removes a key binding.").arg(
+            "key",
+            ArgType::String,
+            "The key to unbind.",
+        ),
+        |console, args| {
+            let chord: KeyChord = args.string("key")?.parse()?;
+            let mut bindings = BINDINGS.lock().unwrap();
+            bindings
+                .unbind(&chord)
+                .ok_or_else(|| anyhow::anyhow!("`{}` is not bound.", chord))?;
+            save_after_change(console, &bindings);
+            Ok(())
+        },
+        |_, args| match args {
+            [] => BINDINGS
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(chord, _)| chord.to_string())
+                .collect(),
+            _ => vec![],
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_loading_after_bad_lines() {
+        let path =
+            std::env::temp_dir().join(format!("bindings-{}-bad-lines.cfg", std::process::id()));
+        fs::write(
+            &path,
+            "bind F5 \"echo a\"\nbind nope echo\nunbind F6\nbind ctrl+F7 \"echo b\"\n",
+        )
+        .unwrap();
+
+        let mut bindings = Bindings::new(Some(path.clone()));
+        let error = bindings.load().unwrap_err().to_string();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(error.lines().count(), 2);
+        assert!(error.contains(":2: `nope` is not a key that can be bound."));
+        assert!(error.contains(":3: expected `bind <key> <command line>`"));
+        let loaded: Vec<_> = bindings
+            .iter()
+            .map(|(chord, line)| (chord.to_string(), line.as_str()))
+            .collect();
+        assert_eq!(
+            loaded,
+            [
+                ("F5".to_owned(), "echo a"),
+                ("ctrl+F7".to_owned(), "echo b")
+            ]
+        );
+    }
+}
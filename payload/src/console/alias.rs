@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use parking_lot::Mutex;
+
+use super::{tokenizer, ArgType, CommandHandle, CommandSignature, Console};
+
+struct Alias {
+    line: String,
+    handle: CommandHandle,
+}
+
+/// Registers `name` as a command that runs `line`, with any arguments it is given appended
+/// to the last command on the line.
+fn add_alias(
+    console: &mut Console,
+    aliases: &Mutex<BTreeMap<String, Alias>>,
+    name: &str,
+    line: &str,
+) -> anyhow::Result<()> {
+    let mut aliases = aliases.lock();
+    if console.commands.contains_key(name) && !aliases.contains_key(name) {
+        anyhow::bail!("`{}` is already a command.", name);
+    }
+
+    let commands = tokenizer::tokenize(line)?;
+    anyhow::ensure!(
+        !commands.is_empty(),
+        "The alias `{}` has no commands.",
+        name
+    );
+
+    let source = format!("alias {}", name);
+    let handle = console.add_command(name, move |console, _, args| {
+        let mut commands = commands.clone();
+        if let Some(last) = commands.last_mut() {
+            last.extend(args.iter().map(|s| s.to_string()));
+        }
+        console.queue_commands(Some(source.clone()), commands);
+        Ok(())
+    });
+
+    aliases.insert(
+        name.to_owned(),
+        Alias {
+            line: line.to_owned(),
+            handle,
+        },
+    );
+    Ok(())
+}
+
+pub fn register_commands(console: &mut Console) {
+    let aliases: Arc<Mutex<BTreeMap<String, Alias>>> = Default::default();
+
+    console.register_command(
+        CommandSignature::new("alias", "Defines a command that runs a command line.")
+            .optional_arg("name", ArgType::String, "", "The name of the new command.")
+            .rest("line", ArgType::String, "The command line to run."),
+        {
+            let aliases = aliases.clone();
+            move |console, args| {
+                let name = args.string("name")?;
+                let line = args.rest_line();
+
+                if name.is_empty() || line.is_empty() {
+                    let lines: Vec<_> = aliases
+                        .lock()
+                        .iter()
+                        .filter(|(alias, _)| name.is_empty() || alias.as_str() == name)
+                        .map(|(alias, Alias { line, .. })| format!("{} = {}", alias, line))
+                        .collect();
+                    for line in lines {
+                        console.push_back_info(line);
+                    }
+                    return Ok(());
+                }
+
+                add_alias(console, &aliases, name, &line)
+            }
+        },
+    );
+
+    console.register_command_with_completer(
+        CommandSignature::new("unalias", "Removes a command defined with `alias`.").arg(
+            "name",
+            ArgType::String,
+            "The alias to remove.",
+        ),
+        {
+            let aliases = aliases.clone();
+            move |console, args| {
+                let name = args.string("name")?;
+                let alias = aliases
+                    .lock()
+                    .remove(name)
+                    .ok_or_else(|| anyhow::anyhow!("The alias `{}` does not exist.", name))?;
+                console.remove_command(alias.handle);
+                Ok(())
+            }
+        },
+        move |_, args| match args {
+            [] => aliases.lock().keys().cloned().collect(),
+            _ => vec![],
+        },
+    );
+}
@@ -0,0 +1,634 @@
+use std::{collections::HashMap, fmt};
+
+use super::expr::{self, Environment};
+
+/// The type an argument is parsed as before being passed to a command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArgType {
+    /// An integer, or an expression that evaluates to one.
+    Int,
+    Float,
+    /// An address expression, such as `HITMAN3.exe+0x1A2B3C`. As everywhere else, numbers
+    /// are decimal unless they start with `0x`.
+    Address,
+    Bool,
+    String,
+    /// One of a fixed set of strings.
+    Enum(&'static [&'static str]),
+}
+
+impl ArgType {
+    /// Parses `text`, using `env` to evaluate any expression.
+    pub fn parse(&self, text: &str, env: &dyn Environment) -> Result<Value, String> {
+        match self {
+            ArgType::Int => match parse_int(text) {
+                Some(value) => Ok(Value::Int(value)),
+                None => expr::evaluate(text, env)
+                    .map(|value| Value::Int(value as i64))
+                    .map_err(|e| format!("`{}` is not an integer: {}", text, e)),
+            },
+            ArgType::Float => text
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| format!("`{}` is not a number", text)),
+            ArgType::Address => expr::evaluate(text, env)
+                .map(|address| Value::Address(address as usize))
+                .map_err(|e| format!("`{}` is not an address: {}", text, e)),
+            ArgType::Bool => match text.to_ascii_lowercase().as_str() {
+                "1" | "true" | "on" | "yes" => Ok(Value::Bool(true)),
+                "0" | "false" | "off" | "no" => Ok(Value::Bool(false)),
+                _ => Err(format!("`{}` is not a boolean", text)),
+            },
+            ArgType::String => Ok(Value::String(text.to_owned())),
+            ArgType::Enum(choices) => choices
+                .iter()
+                .find(|c| c.eq_ignore_ascii_case(text))
+                .map(|c| Value::String(c.to_string()))
+                .ok_or_else(|| format!("`{}` is not one of {}", text, choices.join("|"))),
+        }
+    }
+
+    /// How values of this type are written, for `help <command>`.
+    fn help(&self) -> Option<&'static str> {
+        match self {
+            ArgType::Address => Some("Numbers are decimal unless they start with `0x`."),
+            _ => None,
+        }
+    }
+
+    /// The values offered when completing an argument of this type.
+    pub fn candidates(&self) -> Vec<String> {
+        match self {
+            ArgType::Bool => vec!["false".into(), "true".into()],
+            ArgType::Enum(choices) => choices.iter().map(|c| c.to_string()).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+impl fmt::Display for ArgType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgType::Int => write!(f, "int"),
+            ArgType::Float => write!(f, "float"),
+            ArgType::Address => write!(f, "address"),
+            ArgType::Bool => write!(f, "bool"),
+            ArgType::String => write!(f, "string"),
+            ArgType::Enum(choices) => write!(f, "{}", choices.join("|")),
+        }
+    }
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal integer.
+fn parse_int(text: &str) -> Option<i64> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(digits) => i64::from_str_radix(digits, 16).ok()?,
+        None => text.parse().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// A parsed argument value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Address(usize),
+    Bool(bool),
+    String(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Address(value) => write!(f, "0x{:X}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+#[derive(Clone)]
+struct ArgSpec {
+    name: &'static str,
+    ty: ArgType,
+    description: &'static str,
+    default: Option<Value>,
+}
+
+impl ArgSpec {
+    fn usage(&self) -> String {
+        match &self.default {
+            Some(default) => format!("[{}:{}={}]", self.name, self.ty, default),
+            None => format!("<{}:{}>", self.name, self.ty),
+        }
+    }
+}
+
+/// Describes a command's arguments, so that they can be validated and converted before
+/// the command is called, and so that the command can be listed by `help`.
+#[derive(Clone)]
+pub struct CommandSignature {
+    name: String,
+    description: &'static str,
+    positional: Vec<ArgSpec>,
+    named: Vec<ArgSpec>,
+    rest: Option<ArgSpec>,
+}
+
+impl CommandSignature {
+    pub fn new(name: &str, description: &'static str) -> Self {
+        Self {
+            name: name.to_owned(),
+            description,
+            positional: vec![],
+            named: vec![],
+            rest: None,
+        }
+    }
+
+    /// Adds a required positional argument.
+    pub fn arg(mut self, name: &'static str, ty: ArgType, description: &'static str) -> Self {
+        self.positional.push(ArgSpec {
+            name,
+            ty,
+            description,
+            default: None,
+        });
+        self
+    }
+
+    /// Adds a positional argument that takes `default` when omitted. Optional arguments
+    /// must come after all of the required ones.
+    pub fn optional_arg(
+        mut self,
+        name: &'static str,
+        ty: ArgType,
+        default: impl Into<Value>,
+        description: &'static str,
+    ) -> Self {
+        self.positional.push(ArgSpec {
+            name,
+            ty,
+            description,
+            default: Some(default.into()),
+        });
+        self
+    }
+
+    /// Adds an argument passed as `--name value` or `--name=value`. Boolean arguments
+    /// can also be passed as a bare `--name`.
+    pub fn named_arg(
+        mut self,
+        name: &'static str,
+        ty: ArgType,
+        default: impl Into<Value>,
+        description: &'static str,
+    ) -> Self {
+        self.named.push(ArgSpec {
+            name,
+            ty,
+            description,
+            default: Some(default.into()),
+        });
+        self
+    }
+
+    /// Collects any remaining positional arguments.
+    pub fn rest(mut self, name: &'static str, ty: ArgType, description: &'static str) -> Self {
+        self.rest = Some(ArgSpec {
+            name,
+            ty,
+            description,
+            default: None,
+        });
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        self.description
+    }
+
+    /// Whether the command cannot be run without any arguments.
+    pub fn has_required_args(&self) -> bool {
+        self.positional.iter().any(|arg| arg.default.is_none())
+    }
+
+    pub fn usage(&self) -> String {
+        let mut usage = self.name.clone();
+        for arg in &self.positional {
+            usage.push(' ');
+            usage.push_str(&arg.usage());
+        }
+        for arg in &self.named {
+            if let Some(default) = &arg.default {
+                usage.push_str(&format!(" [--{}:{}={}]", arg.name, arg.ty, default));
+            }
+        }
+        if let Some(rest) = &self.rest {
+            usage.push_str(&format!(" [{}:{}...]", rest.name, rest.ty));
+        }
+        usage
+    }
+
+    /// A line per argument describing it, for `help <command>`.
+    pub fn argument_help(&self) -> Vec<String> {
+        let named = self
+            .named
+            .iter()
+            .map(|arg| (format!("--{}", arg.name), arg));
+        self.positional
+            .iter()
+            .map(|arg| (arg.name.to_owned(), arg))
+            .chain(named)
+            .chain(
+                self.rest
+                    .iter()
+                    .map(|arg| (format!("{}...", arg.name), arg)),
+            )
+            .map(|(name, arg)| match arg.ty.help() {
+                Some(help) => format!("  {} ({}): {} {}", name, arg.ty, arg.description, help),
+                None => format!("  {} ({}): {}", name, arg.ty, arg.description),
+            })
+            .collect()
+    }
+
+    /// The values offered when completing the argument following `words`.
+    pub fn candidates(&self, words: &[&str]) -> Vec<String> {
+        let mut positional = 0;
+        let mut words = words.iter();
+        while let Some(word) = words.next() {
+            match self.find_named(word) {
+                Some((arg, None)) if arg.ty != ArgType::Bool => {
+                    words.next();
+                }
+                Some(_) => {}
+                None => positional += 1,
+            }
+        }
+
+        let mut candidates: Vec<_> = self
+            .named
+            .iter()
+            .map(|arg| format!("--{}", arg.name))
+            .collect();
+        if let Some(arg) = self.positional.get(positional).or(self.rest.as_ref()) {
+            candidates.extend(arg.ty.candidates());
+        }
+        candidates
+    }
+
+    fn find_named<'a>(&self, word: &'a str) -> Option<(&ArgSpec, Option<&'a str>)> {
+        let word = word.strip_prefix("--")?;
+        let (name, value) = match word.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (word, None),
+        };
+        self.named
+            .iter()
+            .find(|arg| arg.name == name)
+            .map(|arg| (arg, value))
+    }
+
+    /// Validates and converts `words` according to this signature, using `env` to evaluate
+    /// expressions.
+    pub fn parse(&self, words: &[&str], env: &dyn Environment) -> Result<Args, String> {
+        let mut args = Args::default();
+        let mut positional = self.positional.iter();
+
+        let mut words = words.iter();
+        while let Some(word) = words.next() {
+            if let Some((arg, value)) = self.find_named(word) {
+                let value = match (value, arg.ty) {
+                    (Some(value), _) => value,
+                    (None, ArgType::Bool) => "true",
+                    (None, _) => words
+                        .next()
+                        .ok_or_else(|| format!("Missing value for `--{}`", arg.name))?,
+                };
+                let value = arg
+                    .ty
+                    .parse(value, env)
+                    .map_err(|e| format!("--{}: {}", arg.name, e))?;
+                args.values.insert(arg.name, value);
+            } else if let Some(arg) = positional.next() {
+                let value = arg
+                    .ty
+                    .parse(word, env)
+                    .map_err(|e| format!("{}: {}", arg.name, e))?;
+                args.values.insert(arg.name, value);
+            } else if let Some(rest) = &self.rest {
+                let value = rest
+                    .ty
+                    .parse(word, env)
+                    .map_err(|e| format!("{}: {}", rest.name, e))?;
+                args.rest.push(value);
+            } else {
+                return Err(format!("Unexpected argument `{}`", word));
+            }
+        }
+
+        for arg in positional.chain(&self.named) {
+            if args.values.contains_key(arg.name) {
+                continue;
+            }
+            match &arg.default {
+                Some(default) => args.values.insert(arg.name, default.clone()),
+                None => return Err(format!("Missing argument `{}`", arg.name)),
+            };
+        }
+
+        Ok(args)
+    }
+}
+
+/// The converted arguments passed to a command registered with a [`CommandSignature`].
+#[derive(Default, Debug)]
+pub struct Args {
+    values: HashMap<&'static str, Value>,
+    rest: Vec<Value>,
+}
+
+impl Args {
+    pub fn get(&self, name: &str) -> anyhow::Result<&Value> {
+        self.values
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("No argument named `{}`", name))
+    }
+
+    pub fn int(&self, name: &str) -> anyhow::Result<i64> {
+        match self.get(name)? {
+            Value::Int(value) => Ok(*value),
+            value => anyhow::bail!("Argument `{}` is not an integer: {}", name, value),
+        }
+    }
+
+    pub fn float(&self, name: &str) -> anyhow::Result<f64> {
+        match self.get(name)? {
+            Value::Float(value) => Ok(*value),
+            Value::Int(value) => Ok(*value as f64),
+            value => anyhow::bail!("Argument `{}` is not a number: {}", name, value),
+        }
+    }
+
+    pub fn address(&self, name: &str) -> anyhow::Result<usize> {
+        match self.get(name)? {
+            Value::Address(value) => Ok(*value),
+            value => anyhow::bail!("Argument `{}` is not an address: {}", name, value),
+        }
+    }
+
+    pub fn bool(&self, name: &str) -> anyhow::Result<bool> {
+        match self.get(name)? {
+            Value::Bool(value) => Ok(*value),
+            value => anyhow::bail!("Argument `{}` is not a boolean: {}", name, value),
+        }
+    }
+
+    pub fn string(&self, name: &str) -> anyhow::Result<&str> {
+        match self.get(name)? {
+            Value::String(value) => Ok(value),
+            value => anyhow::bail!("Argument `{}` is not a string: {}", name, value),
+        }
+    }
+
+    /// The arguments collected by [`CommandSignature::rest`].
+    pub fn rest(&self) -> &[Value] {
+        &self.rest
+    }
+
+    /// The arguments collected by [`CommandSignature::rest`] as a command line. A single
+    /// argument is taken as the whole line, as in `alias a "echo a; echo b"`; otherwise,
+    /// the arguments are requoted so that they tokenize the same way.
+    pub fn rest_line(&self) -> String {
+        match &self.rest[..] {
+            [line] => line.to_string(),
+            words => words
+                .iter()
+                .map(|v| super::tokenizer::quote(&v.to_string()))
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An environment where `base` is at 0x1000.
+    struct TestEnvironment;
+
+    impl Environment for TestEnvironment {
+        fn symbol(&self, name: &str) -> Option<u64> {
+            (name == "base").then_some(0x1000)
+        }
+
+        fn read_pointer(&self, address: u64) -> anyhow::Result<u64> {
+            anyhow::bail!("Cannot read memory at 0x{:X} here.", address)
+        }
+    }
+
+    fn address(text: &str) -> Result<Value, String> {
+        ArgType::Address.parse(text, &TestEnvironment)
+    }
+
+    #[test]
+    fn addresses_are_decimal_unless_prefixed() {
+        assert_eq!(address("10"), Ok(Value::Address(10)));
+        assert_eq!(address("0x10"), Ok(Value::Address(0x10)));
+        assert_eq!(address("10+0"), Ok(Value::Address(10)));
+        assert_eq!(address("base+10"), Ok(Value::Address(0x1000 + 10)));
+        assert_eq!(address("base+0x10"), Ok(Value::Address(0x1010)));
+        assert!(address("1A").is_err());
+    }
+
+    #[test]
+    fn address_help_gives_number_rule() {
+        let signature =
+            CommandSignature::new("mem", "").arg("address", ArgType::Address, "Where to look.");
+        assert_eq!(
+            signature.argument_help(),
+            ["  address (address): Where to look. Numbers are decimal unless they start with `0x`."]
+        );
+    }
+
+    fn signature() -> CommandSignature {
+        CommandSignature::new("spawn", "Spawns things.")
+            .arg("what", ArgType::String, "What to spawn.")
+            .optional_arg("count", ArgType::Int, 1_i64, "How many to spawn.")
+            .named_arg("scale", ArgType::Float, 1.0, "How big they are.")
+            .named_arg("visible", ArgType::Bool, true, "Whether they can be seen.")
+    }
+
+    fn parse(signature: &CommandSignature, words: &[&str]) -> Result<Args, String> {
+        signature.parse(words, &TestEnvironment)
+    }
+
+    #[test]
+    fn parses_required_and_defaulted_args() {
+        let args = parse(&signature(), &["crate"]).unwrap();
+        assert_eq!(args.string("what").unwrap(), "crate");
+        assert_eq!(args.int("count").unwrap(), 1);
+        assert_eq!(args.float("scale").unwrap(), 1.0);
+        assert!(args.bool("visible").unwrap());
+
+        let args = parse(
+            &signature(),
+            &["crate", "3", "--scale", "2.5", "--visible=off"],
+        )
+        .unwrap();
+        assert_eq!(args.int("count").unwrap(), 3);
+        assert_eq!(args.float("scale").unwrap(), 2.5);
+        assert!(!args.bool("visible").unwrap());
+    }
+
+    #[test]
+    fn bare_bool_flags_are_true() {
+        let signature =
+            CommandSignature::new("list", "").named_arg("all", ArgType::Bool, false, "");
+        assert!(parse(&signature, &["--all"]).unwrap().bool("all").unwrap());
+        assert!(!parse(&signature, &[]).unwrap().bool("all").unwrap());
+    }
+
+    #[test]
+    fn reports_missing_and_unexpected_args() {
+        assert_eq!(
+            parse(&signature(), &[]).unwrap_err(),
+            "Missing argument `what`"
+        );
+        assert_eq!(
+            parse(&signature(), &["crate", "1", "extra"]).unwrap_err(),
+            "Unexpected argument `extra`"
+        );
+        assert_eq!(
+            parse(&signature(), &["crate", "--scale"]).unwrap_err(),
+            "Missing value for `--scale`"
+        );
+        assert_eq!(
+            parse(&signature(), &["crate", "many"]).unwrap_err(),
+            "count: `many` is not an integer: Unknown symbol `many`."
+        );
+        assert_eq!(
+            parse(&signature(), &["crate", "--scale", "big"]).unwrap_err(),
+            "--scale: `big` is not a number"
+        );
+    }
+
+    #[test]
+    fn collects_rest() {
+        let signature =
+            CommandSignature::new("echo", "").rest("text", ArgType::String, "The text.");
+        let args = parse(&signature, &["a b", "c"]).unwrap();
+        assert_eq!(args.rest(), ["a b".into(), "c".into()]);
+        assert_eq!(args.rest_line(), "\"a b\" c");
+        assert_eq!(parse(&signature, &["a; b"]).unwrap().rest_line(), "a; b");
+        assert!(parse(&signature, &[]).unwrap().rest().is_empty());
+
+        let signature = CommandSignature::new("sum", "").rest("values", ArgType::Int, "");
+        assert_eq!(
+            parse(&signature, &["1", "x"]).unwrap_err(),
+            "values: `x` is not an integer: Unknown symbol `x`."
+        );
+    }
+
+    #[test]
+    fn matches_enum_choices() {
+        let ty = ArgType::Enum(&["start", "stop"]);
+        assert_eq!(
+            ty.parse("STOP", &TestEnvironment),
+            Ok(Value::String("stop".into()))
+        );
+        assert_eq!(
+            ty.parse("pause", &TestEnvironment),
+            Err("`pause` is not one of start|stop".into())
+        );
+        assert_eq!(ty.candidates(), ["start", "stop"]);
+    }
+
+    #[test]
+    fn parses_bool_spellings() {
+        for text in ["1", "true", "On", "YES"] {
+            assert_eq!(
+                ArgType::Bool.parse(text, &TestEnvironment),
+                Ok(Value::Bool(true))
+            );
+        }
+        for text in ["0", "False", "off", "no"] {
+            assert_eq!(
+                ArgType::Bool.parse(text, &TestEnvironment),
+                Ok(Value::Bool(false))
+            );
+        }
+        assert!(ArgType::Bool.parse("maybe", &TestEnvironment).is_err());
+    }
+
+    #[test]
+    fn parses_ints() {
+        assert_eq!(parse_int("42"), Some(42));
+        assert_eq!(parse_int("-42"), Some(-42));
+        assert_eq!(parse_int("0x2A"), Some(42));
+        assert_eq!(parse_int("0X2a"), Some(42));
+        assert_eq!(parse_int("-0x2A"), Some(-42));
+        assert_eq!(parse_int("2A"), None);
+        assert_eq!(parse_int("0x"), None);
+
+        let int = |text| ArgType::Int.parse(text, &TestEnvironment);
+        assert_eq!(int("-0x10"), Ok(Value::Int(-16)));
+        assert_eq!(int("base+1"), Ok(Value::Int(0x1001)));
+    }
+
+    #[test]
+    fn describes_usage() {
+        assert_eq!(
+            signature().usage(),
+            "spawn <what:string> [count:int=1] [--scale:float=1] [--visible:bool=true]"
+        );
+        let signature = CommandSignature::new("echo", "").rest("text", ArgType::String, "");
+        assert_eq!(signature.usage(), "echo [text:string...]");
+        assert!(!signature.has_required_args());
+    }
+
+    #[test]
+    fn offers_candidates_for_the_next_arg() {
+        let signature = CommandSignature::new("rcon", "")
+            .optional_arg("action", ArgType::Enum(&["start", "stop"]), "start", "")
+            .named_arg("verbose", ArgType::Bool, false, "");
+        assert_eq!(signature.candidates(&[]), ["--verbose", "start", "stop"]);
+        assert_eq!(signature.candidates(&["start"]), ["--verbose"]);
+    }
+}
@@ -0,0 +1,145 @@
+use super::tokenizer::{quote, tokenize_partial};
+
+/// An in-progress Tab completion of the last word on the input line.
+pub struct Completion {
+    /// The input line up to the start of the word being completed.
+    prefix: String,
+    candidates: Vec<String>,
+    index: usize,
+}
+
+impl Completion {
+    /// Returns `None` if there is nothing to complete the word with.
+    pub fn new(prefix: String, candidates: Vec<String>) -> Option<Self> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            prefix,
+            candidates,
+            index: 0,
+        })
+    }
+
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.candidates.len() == 1
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.index = index % self.candidates.len();
+    }
+
+    pub fn cycle(&mut self) {
+        self.select(self.index + 1);
+    }
+
+    /// The input line with the selected candidate filled in, quoted if it needs to be.
+    pub fn line(&self) -> String {
+        format!("{}{}", self.prefix, quote(&self.candidates[self.index]))
+    }
+}
+
+/// The last command on a partly typed input line, split for completing its last word.
+#[derive(Debug, PartialEq)]
+pub struct SplitInput {
+    /// The words before the one under completion. The first is the command keyword, and
+    /// there are none if the keyword itself is under completion.
+    pub args: Vec<String>,
+    /// The word under completion, without its quotes and escapes.
+    pub partial: String,
+    /// Where the word under completion starts in the input, including any opening quote.
+    pub start: usize,
+}
+
+/// Splits the last command in `input` into the arguments preceding the word under
+/// completion and that word, tokenized as the command will be when it is run.
+pub fn split_input(input: &str) -> SplitInput {
+    let (args, word) = tokenize_partial(input);
+    let (start, partial) = word.unwrap_or((input.len(), String::new()));
+    SplitInput {
+        args,
+        partial,
+        start,
+    }
+}
+
+/// Keeps the candidates that start with `partial`, sorted and without duplicates.
+pub fn filter_candidates(candidates: Vec<String>, partial: &str) -> Vec<String> {
+    let mut candidates: Vec<_> = candidates
+        .into_iter()
+        .filter(|c| c.starts_with(partial))
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(args: &[&str], partial: &str, start: usize) -> SplitInput {
+        SplitInput {
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            partial: partial.to_owned(),
+            start,
+        }
+    }
+
+    #[test]
+    fn splits_keyword_and_arguments() {
+        assert_eq!(split_input(""), split(&[], "", 0));
+        assert_eq!(split_input("he"), split(&[], "he", 0));
+        assert_eq!(split_input("help "), split(&["help"], "", 5));
+        assert_eq!(split_input("set log_f"), split(&["set"], "log_f", 4));
+    }
+
+    #[test]
+    fn completes_the_last_command() {
+        assert_eq!(split_input("echo a; he"), split(&[], "he", 8));
+        assert_eq!(split_input("echo a;"), split(&[], "", 7));
+        assert_eq!(split_input("echo a; set x"), split(&["set"], "x", 12));
+    }
+
+    #[test]
+    fn keeps_quoted_words_together() {
+        assert_eq!(split_input(r#"exec "my fi"#), split(&["exec"], "my fi", 5));
+        assert_eq!(
+            split_input(r#"echo "a b" c"#),
+            split(&["echo", "a b"], "c", 11)
+        );
+        assert_eq!(
+            split_input(r#"echo "a; b" "#),
+            split(&["echo", "a; b"], "", 12)
+        );
+        assert_eq!(split_input(r"exec my\ fi"), split(&["exec"], "my fi", 5));
+    }
+
+    #[test]
+    fn quotes_candidates_that_need_it() {
+        let completion = Completion::new(
+            "exec ".into(),
+            vec!["my file.cfg".into(), "plain.cfg".into()],
+        )
+        .unwrap();
+        assert_eq!(completion.line(), r#"exec "my file.cfg""#);
+        let mut completion = completion;
+        completion.cycle();
+        assert_eq!(completion.line(), "exec plain.cfg");
+    }
+
+    #[test]
+    fn filters_sorts_and_dedups_candidates() {
+        let candidates = vec!["set".into(), "echo".into(), "exec".into(), "exec".into()];
+        assert_eq!(filter_candidates(candidates, "e"), ["echo", "exec"]);
+    }
+}
@@ -0,0 +1,632 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::PathBuf,
+};
+
+use super::{tokenizer, ArgType, Color, CommandSignature, Console};
+
+pub const CVARS_FILENAME: &str = "cvars.cfg";
+
+/// The value of a console variable. The type of a variable is fixed by its default value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CvarValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Color),
+}
+
+impl CvarValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            CvarValue::Bool(_) => "bool",
+            CvarValue::Int(_) => "int",
+            CvarValue::Float(_) => "float",
+            CvarValue::String(_) => "string",
+            CvarValue::Color(_) => "color",
+        }
+    }
+
+    /// Parses `text` as a value of the same type as `self`.
+    fn parse_as(&self, text: &str) -> Result<CvarValue, String> {
+        match self {
+            CvarValue::Bool(_) => match text.to_ascii_lowercase().as_str() {
+                "1" | "true" | "on" | "yes" => Ok(CvarValue::Bool(true)),
+                "0" | "false" | "off" | "no" => Ok(CvarValue::Bool(false)),
+                _ => Err(format!("`{}` is not a boolean", text)),
+            },
+            CvarValue::Int(_) => text
+                .parse()
+                .map(CvarValue::Int)
+                .map_err(|_| format!("`{}` is not an integer", text)),
+            CvarValue::Float(_) => text
+                .parse()
+                .map(CvarValue::Float)
+                .map_err(|_| format!("`{}` is not a number", text)),
+            CvarValue::String(_) => Ok(CvarValue::String(text.to_owned())),
+            CvarValue::Color(_) => parse_color(text)
+                .map(CvarValue::Color)
+                .ok_or_else(|| format!("`{}` is not a colour like #RRGGBB or r,g,b", text)),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            CvarValue::Int(value) => Some(*value as f64),
+            CvarValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CvarValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CvarValue::Bool(value) => write!(f, "{}", value),
+            CvarValue::Int(value) => write!(f, "{}", value),
+            CvarValue::Float(value) => write!(f, "{}", value),
+            CvarValue::String(value) => write!(f, "{}", value),
+            CvarValue::Color((r, g, b)) => write!(f, "#{:02X}{:02X}{:02X}", r, g, b),
+        }
+    }
+}
+
+impl From<bool> for CvarValue {
+    fn from(value: bool) -> Self {
+        CvarValue::Bool(value)
+    }
+}
+
+impl From<i64> for CvarValue {
+    fn from(value: i64) -> Self {
+        CvarValue::Int(value)
+    }
+}
+
+impl From<f64> for CvarValue {
+    fn from(value: f64) -> Self {
+        CvarValue::Float(value)
+    }
+}
+
+impl From<&str> for CvarValue {
+    fn from(value: &str) -> Self {
+        CvarValue::String(value.to_owned())
+    }
+}
+
+impl From<Color> for CvarValue {
+    fn from(value: Color) -> Self {
+        CvarValue::Color(value)
+    }
+}
+
+fn parse_color(text: &str) -> Option<Color> {
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some((channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    let channels: Vec<_> = text
+        .split(',')
+        .map(|c| c.trim().parse::<u8>().ok())
+        .collect::<Option<_>>()?;
+    match channels[..] {
+        [r, g, b] => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+type ChangeCallback = Box<dyn FnMut(&CvarValue) + Send>;
+
+/// A named, typed variable that can be inspected and changed from the console.
+pub struct Cvar {
+    name: String,
+    description: &'static str,
+    default: CvarValue,
+    value: CvarValue,
+    min: Option<f64>,
+    max: Option<f64>,
+    on_change: Vec<ChangeCallback>,
+}
+
+impl Cvar {
+    pub fn new(name: &str, description: &'static str, default: impl Into<CvarValue>) -> Self {
+        let default = default.into();
+        Self {
+            name: name.to_owned(),
+            description,
+            value: default.clone(),
+            default,
+            min: None,
+            max: None,
+            on_change: vec![],
+        }
+    }
+
+    /// Restricts a numeric variable to `min..=max`.
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Calls `callback` with the new value whenever the variable changes.
+    pub fn on_change(mut self, callback: impl FnMut(&CvarValue) + Send + 'static) -> Self {
+        self.on_change.push(Box::new(callback));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        self.description
+    }
+
+    pub fn value(&self) -> &CvarValue {
+        &self.value
+    }
+
+    pub fn default(&self) -> &CvarValue {
+        &self.default
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.value == self.default
+    }
+
+    fn set(&mut self, value: CvarValue) -> Result<(), String> {
+        if std::mem::discriminant(&value) != std::mem::discriminant(&self.default) {
+            return Err(format!(
+                "`{}` is a {}, not a {}",
+                self.name,
+                self.default.type_name(),
+                value.type_name()
+            ));
+        }
+
+        if let Some(number) = value.as_f64() {
+            let below = self.min.is_some_and(|min| number < min);
+            let above = self.max.is_some_and(|max| number > max);
+            if below || above {
+                return Err(format!(
+                    "`{}` must be between {} and {}",
+                    self.name,
+                    self.min.unwrap_or(f64::NEG_INFINITY),
+                    self.max.unwrap_or(f64::INFINITY)
+                ));
+            }
+        }
+
+        if value != self.value {
+            self.value = value;
+            for callback in &mut self.on_change {
+                callback(&self.value);
+            }
+        }
+        Ok(())
+    }
+
+    fn set_from_str(&mut self, text: &str) -> Result<(), String> {
+        let value = self.default.parse_as(text)?;
+        self.set(value)
+    }
+}
+
+/// All of the registered console variables, and the values saved for them.
+pub struct CvarRegistry {
+    cvars: BTreeMap<String, Cvar>,
+    /// Saved values for variables that have not been registered yet.
+    pending: HashMap<String, String>,
+    path: Option<PathBuf>,
+}
+
+impl CvarRegistry {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            cvars: BTreeMap::new(),
+            pending: HashMap::new(),
+            path,
+        }
+    }
+
+    /// Registers a variable, applying any value saved for it. Registering a variable with
+    /// the name of an existing one replaces it.
+    pub fn register(&mut self, mut cvar: Cvar) -> anyhow::Result<()> {
+        let saved = self.pending.remove(&cvar.name);
+        let result = match saved {
+            Some(text) => cvar
+                .set_from_str(&text)
+                .map_err(|e| anyhow::anyhow!("Ignoring saved value: {}", e)),
+            None => Ok(()),
+        };
+
+        self.cvars.insert(cvar.name.clone(), cvar);
+        result
+    }
+
+    /// Removes a variable, keeping its value so that it is saved and restored if the
+    /// variable is registered again.
+    pub fn unregister(&mut self, name: &str) -> Option<Cvar> {
+        let cvar = self.cvars.remove(name)?;
+        if !cvar.is_default() {
+            self.pending
+                .insert(cvar.name.clone(), cvar.value.to_string());
+        }
+        Some(cvar)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cvar> {
+        self.cvars.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cvar> {
+        self.cvars.values()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.cvars.keys().cloned().collect()
+    }
+
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)?.value() {
+            CvarValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.get(name)?.value() {
+            CvarValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, name: &str) -> Option<f64> {
+        self.get(name)?.value().as_f64()
+    }
+
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)?.value() {
+            CvarValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self, name: &str) -> Option<Color> {
+        match self.get(name)?.value() {
+            CvarValue::Color(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: impl Into<CvarValue>) -> anyhow::Result<()> {
+        self.get_mut(name)?
+            .set(value.into())
+            .map_err(anyhow::Error::msg)
+    }
+
+    pub fn set_from_str(&mut self, name: &str, text: &str) -> anyhow::Result<()> {
+        self.get_mut(name)?
+            .set_from_str(text)
+            .map_err(anyhow::Error::msg)
+    }
+
+    pub fn toggle(&mut self, name: &str) -> anyhow::Result<bool> {
+        let cvar = self.get_mut(name)?;
+        match cvar.value {
+            CvarValue::Bool(value) => {
+                cvar.set(CvarValue::Bool(!value))
+                    .map_err(anyhow::Error::msg)?;
+                Ok(!value)
+            }
+            _ => anyhow::bail!("`{}` is not a bool", name),
+        }
+    }
+
+    pub fn reset(&mut self, name: &str) -> anyhow::Result<()> {
+        let cvar = self.get_mut(name)?;
+        let default = cvar.default.clone();
+        cvar.set(default).map_err(anyhow::Error::msg)
+    }
+
+    fn get_mut(&mut self, name: &str) -> anyhow::Result<&mut Cvar> {
+        self.cvars
+            .get_mut(name)
+            .ok_or_else(|| anyhow::anyhow!("The variable `{}` does not exist.", name))
+    }
+
+    /// Loads saved values from the config file. Values for variables that are not
+    /// registered yet are applied when they are. Lines that cannot be applied are skipped,
+    /// and reported together once the rest have been loaded.
+    pub fn load(&mut self) -> anyhow::Result<()> {
+        let path = match &self.path {
+            Some(path) if path.exists() => path.clone(),
+            _ => return Ok(()),
+        };
+
+        let contents = fs::read_to_string(&path)?;
+        let mut errors = vec![];
+        for (index, line) in contents.lines().enumerate() {
+            if let Err(e) = self.load_line(line) {
+                errors.push(format!("{}:{}: {}", path.display(), index + 1, e));
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => anyhow::bail!("{}", errors.join("\n")),
+        }
+    }
+
+    fn load_line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+            return Ok(());
+        }
+
+        let mut errors = vec![];
+        for words in tokenizer::tokenize(line).map_err(|e| e.to_string())? {
+            let result = match &words[..] {
+                [set, name, value] if set == "set" => match self.cvars.get_mut(name) {
+                    Some(cvar) => cvar.set_from_str(value),
+                    None => {
+                        self.pending.insert(name.clone(), value.clone());
+                        Ok(())
+                    }
+                },
+                _ => Err("expected `set <name> <value>`".to_owned()),
+            };
+            errors.extend(result.err());
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join("; ")),
+        }
+    }
+
+    /// Saves the values of all variables that differ from their defaults, along with any
+    /// saved values for variables that were never registered.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let mut lines: Vec<_> = self
+            .cvars
+            .values()
+            .filter(|cvar| !cvar.is_default())
+            .map(|cvar| (cvar.name.clone(), cvar.value.to_string()))
+            .chain(self.pending.iter().map(|(k, v)| (k.clone(), v.clone())))
+            .map(|(name, value)| format!("set {} {}\n", name, tokenizer::quote(&value)))
+            .collect();
+        lines.sort();
+
+        fs::write(path, lines.concat())?;
+        Ok(())
+    }
+}
+
+fn complete_cvar_names(console: &Console, args: &[&str]) -> Vec<String> {
+    match args {
+        [] => console.cvars().names(),
+        [name] => match console.cvars().get(name).map(|c| c.value()) {
+            Some(CvarValue::Bool(_)) => vec!["false".into(), "true".into()],
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+fn save_after_change(console: &mut Console) {
+    if let Err(err) = console.cvars().save() {
+        console.push_back_error(format!("Failed to save console variables: {}", err));
+    }
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command_with_completer(
+        CommandSignature::new("set", "Sets a console variable.")
+            .arg("name", ArgType::String, "The variable to set.")
+            .arg("value", ArgType::String, "The new value."),
+        |console, args| {
+            let name = args.string("name")?;
+            console
+                .cvars_mut()
+                .set_from_str(name, args.string("value")?)?;
+            save_after_change(console);
+            Ok(())
+        },
+        complete_cvar_names,
+    );
+
+    console.register_command_with_completer(
+        CommandSignature::new("get", "Prints the value of a console variable.").arg(
+            "name",
+            ArgType::String,
+            "The variable to print.",
+        ),
+        |console, args| {
+            let name = args.string("name")?;
+            let cvar = console
+                .cvars()
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("The variable `{}` does not exist.", name))?;
+            let line = format!("{} = {} (default: {})", name, cvar.value(), cvar.default());
+            console.push_back_info(line);
+            Ok(())
+        },
+        complete_cvar_names,
+    );
+
+    console.register_command_with_completer(
+        CommandSignature::new("toggle", "Flips a boolean console variable.").arg(
+            "name",
+            ArgType::String,
+            "The variable to flip.",
+        ),
+        |console, args| {
+            let name = args.string("name")?;
+            let value = console.cvars_mut().toggle(name)?;
+            console.push_back_info(format!("{} = {}", name, value));
+            save_after_change(console);
+            Ok(())
+        },
+        complete_cvar_names,
+    );
+
+    console.register_command_with_completer(
+        CommandSignature::new("reset", "Resets a console variable to its default.").arg(
+            "name",
+            ArgType::String,
+            "The variable to reset.",
+        ),
+        |console, args| {
+            console.cvars_mut().reset(args.string("name")?)?;
+            save_after_change(console);
+            Ok(())
+        },
+        complete_cvar_names,
+    );
+
+    console.register_command(
+        CommandSignature::new("cvarlist", "Lists the console variables.").optional_arg(
+            "filter",
+            ArgType::String,
+            "",
+            "Only list variables whose names contain this.",
+        ),
+        |console, args| {
+            let filter = args.string("filter")?;
+            let lines: Vec<_> = console
+                .cvars()
+                .iter()
+                .filter(|cvar| cvar.name().contains(filter))
+                .map(|cvar| {
+                    format!(
+                        "{}{} = {} - {}",
+                        if cvar.is_default() { "" } else { "*" },
+                        cvar.name(),
+                        cvar.value(),
+                        cvar.description()
+                    )
+                })
+                .collect();
+
+            for line in lines {
+                console.push_back_info(line);
+            }
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A registry saved to a file of its own in the temporary directory, which starts out
+    /// with `contents`.
+    fn registry(test: &str, contents: &str) -> CvarRegistry {
+        let path = std::env::temp_dir().join(format!("cvars-{}-{}.cfg", std::process::id(), test));
+        fs::write(&path, contents).unwrap();
+        CvarRegistry::new(Some(path))
+    }
+
+    fn register_defaults(registry: &mut CvarRegistry) {
+        registry
+            .register(Cvar::new("volume", "", 50_i64).range(0.0, 100.0))
+            .unwrap();
+        registry.register(Cvar::new("enabled", "", false)).unwrap();
+        registry.register(Cvar::new("name", "", "")).unwrap();
+    }
+
+    #[test]
+    fn saves_and_loads_values() {
+        let mut saved = registry("round_trip", "");
+        register_defaults(&mut saved);
+        saved.set("volume", 75_i64).unwrap();
+        saved.set("name", "two words").unwrap();
+        saved.set("enabled", true).unwrap();
+        saved.save().unwrap();
+
+        let mut loaded = CvarRegistry::new(saved.path.clone());
+        register_defaults(&mut loaded);
+        loaded.load().unwrap();
+        assert_eq!(loaded.int("volume"), Some(75));
+        assert_eq!(loaded.string("name"), Some("two words"));
+        assert_eq!(loaded.bool("enabled"), Some(true));
+    }
+
+    #[test]
+    fn saves_only_changed_values() {
+        let mut registry = registry("changed", "");
+        register_defaults(&mut registry);
+        registry.set("volume", 10_i64).unwrap();
+        registry.save().unwrap();
+        let contents = fs::read_to_string(registry.path.as_ref().unwrap()).unwrap();
+        assert_eq!(contents, "set volume 10\n");
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        let mut registry = registry("range", "");
+        register_defaults(&mut registry);
+        assert!(registry.set("volume", 101_i64).is_err());
+        assert!(registry.set("volume", -1_i64).is_err());
+        assert_eq!(registry.int("volume"), Some(50));
+        registry.set("volume", 100_i64).unwrap();
+        assert_eq!(registry.int("volume"), Some(100));
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_type() {
+        let mut registry = registry("types", "");
+        register_defaults(&mut registry);
+        assert!(registry.set_from_str("volume", "loud").is_err());
+        assert!(registry.set("enabled", 1_i64).is_err());
+        assert!(registry.toggle("enabled").unwrap());
+    }
+
+    #[test]
+    fn applies_pending_values_when_registered() {
+        let mut registry = registry("pending", "set later 7\nset later_bad nope\n");
+        registry.load().unwrap();
+        registry.register(Cvar::new("later", "", 0_i64)).unwrap();
+        assert_eq!(registry.int("later"), Some(7));
+
+        assert!(registry
+            .register(Cvar::new("later_bad", "", 0_i64))
+            .is_err());
+        assert_eq!(registry.int("later_bad"), Some(0));
+    }
+
+    #[test]
+    fn keeps_loading_after_bad_lines() {
+        let mut registry = registry(
+            "bad_lines",
+            "set volume 500\nbogus\nset name \"unterminated\nset enabled true\n// comment\n",
+        );
+        register_defaults(&mut registry);
+        let error = registry.load().unwrap_err().to_string();
+        assert_eq!(error.lines().count(), 3);
+        assert!(error
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with(":1: `volume` must be between 0 and 100"));
+        assert!(error.contains(":2: expected `set <name> <value>`"));
+        assert!(error.contains(":3: Unterminated double quote"));
+        assert_eq!(registry.bool("enabled"), Some(true));
+        assert_eq!(registry.int("volume"), Some(50));
+    }
+}
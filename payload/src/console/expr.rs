@@ -0,0 +1,446 @@
+//! Evaluates address arithmetic typed into the console, such as `HITMAN3.exe+0x1A2B3C + 8*3`.
+//!
+//! The grammar, from loosest to tightest binding:
+//! - `|`, then `&`, then `<<` and `>>`, then `+` and `-`, then `*` and `/`
+//! - unary `-`
+//! - decimal and `0x`-prefixed hexadecimal literals, `(expr)`, module names, and `[expr]`,
+//!   which reads the pointer at an address
+//!
+//! Values are 64-bit and wrap on overflow, so `-8` is the same as `0xFFFFFFFFFFFFFFF8`.
+
+use std::fmt;
+
+use super::{ArgType, CommandSignature, Console};
+
+/// Resolves the symbols and memory an expression refers to.
+pub trait Environment {
+    /// Returns the address of the module or other symbol called `name`.
+    fn symbol(&self, name: &str) -> Option<u64>;
+    /// Reads the pointer-sized value at `address`.
+    fn read_pointer(&self, address: u64) -> anyhow::Result<u64>;
+}
+
+/// An environment with no symbols and no readable memory.
+pub struct NoEnvironment;
+
+impl Environment for NoEnvironment {
+    fn symbol(&self, _name: &str) -> Option<u64> {
+        None
+    }
+
+    fn read_pointer(&self, address: u64) -> anyhow::Result<u64> {
+        anyhow::bail!("Cannot read memory at 0x{:X} here.", address)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidNumber {
+        position: usize,
+    },
+    UnexpectedCharacter {
+        character: char,
+        position: usize,
+    },
+    Expected {
+        expected: &'static str,
+        position: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidNumber { position } => {
+                write!(f, "Invalid number at column {}.", position + 1)
+            }
+            ParseError::UnexpectedCharacter {
+                character,
+                position,
+            } => write!(f, "Unexpected `{}` at column {}.", character, position + 1),
+            ParseError::Expected { expected, position } => {
+                write!(f, "Expected {} at column {}.", expected, position + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    And,
+    Or,
+    Shl,
+    Shr,
+}
+
+impl BinaryOp {
+    /// How tightly the operator binds; higher binds tighter.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Shl | BinaryOp::Shr => 3,
+            BinaryOp::Add | BinaryOp::Sub => 4,
+            BinaryOp::Mul | BinaryOp::Div => 5,
+        }
+    }
+
+    fn apply(self, left: u64, right: u64) -> anyhow::Result<u64> {
+        let shift = |value: u64, shift: fn(u64, u32) -> Option<u64>| {
+            u32::try_from(right)
+                .ok()
+                .and_then(|right| shift(value, right))
+                .unwrap_or(0)
+        };
+        Ok(match self {
+            BinaryOp::Add => left.wrapping_add(right),
+            BinaryOp::Sub => left.wrapping_sub(right),
+            BinaryOp::Mul => left.wrapping_mul(right),
+            BinaryOp::Div => left
+                .checked_div(right)
+                .ok_or_else(|| anyhow::anyhow!("Division by zero."))?,
+            BinaryOp::And => left & right,
+            BinaryOp::Or => left | right,
+            BinaryOp::Shl => shift(left, u64::checked_shl),
+            BinaryOp::Shr => shift(left, u64::checked_shr),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Number(u64),
+    Symbol(String),
+    Negate(Box<Expr>),
+    /// The pointer at the address.
+    Deref(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn evaluate(&self, env: &dyn Environment) -> anyhow::Result<u64> {
+        match self {
+            Expr::Number(value) => Ok(*value),
+            Expr::Symbol(name) => env
+                .symbol(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown symbol `{}`.", name)),
+            Expr::Negate(expr) => Ok(expr.evaluate(env)?.wrapping_neg()),
+            Expr::Deref(expr) => env.read_pointer(expr.evaluate(env)?),
+            Expr::Binary(op, left, right) => op.apply(left.evaluate(env)?, right.evaluate(env)?),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(u64),
+    Symbol(String),
+    Op(BinaryOp),
+    Open(char),
+    Close(char),
+}
+
+fn lex(text: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Op(BinaryOp::Add),
+            '-' => Token::Op(BinaryOp::Sub),
+            '*' => Token::Op(BinaryOp::Mul),
+            '/' => Token::Op(BinaryOp::Div),
+            '&' => Token::Op(BinaryOp::And),
+            '|' => Token::Op(BinaryOp::Or),
+            '<' | '>' => {
+                if chars.next_if(|&(_, next)| next == c).is_none() {
+                    return Err(ParseError::UnexpectedCharacter {
+                        character: c,
+                        position,
+                    });
+                }
+                Token::Op(if c == '<' {
+                    BinaryOp::Shl
+                } else {
+                    BinaryOp::Shr
+                })
+            }
+            '(' | '[' => Token::Open(c),
+            ')' | ']' => Token::Close(c),
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut end = position + c.len_utf8();
+                while let Some((index, next)) = chars.next_if(|&(_, next)| {
+                    next.is_ascii_alphanumeric() || next == '_' || next == '.'
+                }) {
+                    end = index + next.len_utf8();
+                }
+
+                let word = &text[position..end];
+                if c.is_ascii_digit() {
+                    let value = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                        Some(digits) => u64::from_str_radix(digits, 16),
+                        None => word.parse(),
+                    };
+                    Token::Number(value.map_err(|_| ParseError::InvalidNumber { position })?)
+                } else {
+                    Token::Symbol(word.to_owned())
+                }
+            }
+            character => {
+                return Err(ParseError::UnexpectedCharacter {
+                    character,
+                    position,
+                })
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    /// The length of the text, which is where running out of tokens is reported.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map_or(self.end, |(position, _)| *position)
+    }
+
+    /// Parses operators that bind at least as tightly as `min_precedence`.
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.index += 1;
+            let right = self.binary(op.precedence() + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position();
+        let token = self.tokens.get(self.index).map(|(_, token)| token.clone());
+        self.index += 1;
+        match token {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Symbol(name)) => Ok(Expr::Symbol(name)),
+            Some(Token::Op(BinaryOp::Sub)) => Ok(Expr::Negate(Box::new(self.unary()?))),
+            Some(Token::Open(open)) => {
+                let expr = self.binary(0)?;
+                let (close, expected) = match open {
+                    '(' => (')', "`)`"),
+                    _ => (']', "`]`"),
+                };
+                if self.peek() != Some(&Token::Close(close)) {
+                    return Err(ParseError::Expected {
+                        expected,
+                        position: self.position(),
+                    });
+                }
+                self.index += 1;
+                Ok(match open {
+                    '(' => expr,
+                    _ => Expr::Deref(Box::new(expr)),
+                })
+            }
+            _ => Err(ParseError::Expected {
+                expected: "a number, symbol or bracket",
+                position,
+            }),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser {
+        tokens: lex(text)?,
+        index: 0,
+        end: text.len(),
+    };
+    let expr = parser.binary(0)?;
+    if parser.peek().is_some() {
+        return Err(ParseError::Expected {
+            expected: "an operator",
+            position: parser.position(),
+        });
+    }
+    Ok(expr)
+}
+
+pub fn evaluate(text: &str, env: &dyn Environment) -> anyhow::Result<u64> {
+    parse(text)?.evaluate(env)
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command(
+        CommandSignature::new(
+            "calc",
+            "Evaluates an expression, printing the result in hexadecimal and decimal.",
+        )
+        .rest(
+            "expression",
+            ArgType::String,
+            "The expression, such as `[HITMAN3.exe+0x1A2B3C] + 8*3`.",
+        ),
+        |console, args| {
+            let text: Vec<_> = args.rest().iter().map(|v| v.to_string()).collect();
+            let value = console.evaluate(&text.join(" "))?;
+            let message = match value as i64 {
+                signed if signed < 0 => format!("0x{:X} = {} = {}", value, value, signed),
+                _ => format!("0x{:X} = {}", value, value),
+            };
+            console.push_back_info(message);
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    struct TestEnvironment {
+        symbols: HashMap<&'static str, u64>,
+        memory: HashMap<u64, u64>,
+    }
+
+    impl Environment for TestEnvironment {
+        fn symbol(&self, name: &str) -> Option<u64> {
+            self.symbols.get(name).copied()
+        }
+
+        fn read_pointer(&self, address: u64) -> anyhow::Result<u64> {
+            self.memory
+                .get(&address)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("unreadable"))
+        }
+    }
+
+    fn env() -> TestEnvironment {
+        TestEnvironment {
+            symbols: HashMap::from([("HITMAN3.exe", 0x1_4000_0000)]),
+            memory: HashMap::from([(0x1_4000_0010, 0x2000), (0x2008, 0x3000)]),
+        }
+    }
+
+    fn eval(text: &str) -> u64 {
+        evaluate(text, &env()).unwrap()
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(eval("42"), 42);
+        assert_eq!(eval("0x1A2B3C"), 0x1A2B3C);
+        assert_eq!(eval("0XFF"), 0xFF);
+        assert_eq!(eval("-8"), -8_i64 as u64);
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7);
+        assert_eq!(eval("(1 + 2) * 3"), 9);
+        assert_eq!(eval("10 - 4 - 3"), 3);
+        assert_eq!(eval("1 << 4 + 1"), 32);
+        assert_eq!(eval("0xF0 | 0x0F & 0x3"), 0xF3);
+        assert_eq!(eval("100 / 7 >> 1"), 7);
+    }
+
+    #[test]
+    fn parses_into_a_tree() {
+        assert_eq!(
+            parse("[a+1]*2"),
+            Ok(Expr::Binary(
+                BinaryOp::Mul,
+                Box::new(Expr::Deref(Box::new(Expr::Binary(
+                    BinaryOp::Add,
+                    Box::new(Expr::Symbol("a".into())),
+                    Box::new(Expr::Number(1)),
+                )))),
+                Box::new(Expr::Number(2)),
+            ))
+        );
+    }
+
+    #[test]
+    fn symbols_and_dereferences() {
+        assert_eq!(
+            eval("HITMAN3.exe+0x1A2B3C + 8*3"),
+            0x1_4000_0000 + 0x1A2B3C + 24
+        );
+        assert_eq!(eval("[HITMAN3.exe + 0x10]"), 0x2000);
+        assert_eq!(eval("[[HITMAN3.exe + 0x10] + 8] + 4"), 0x3004);
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        assert!(evaluate("nope + 1", &env()).is_err());
+        assert!(evaluate("[0x1234]", &env()).is_err());
+        assert!(evaluate("1 / 0", &env()).is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("(1 + 2"),
+            Err(ParseError::Expected {
+                expected: "`)`",
+                position: 6
+            })
+        );
+        assert_eq!(
+            parse("[1 + 2)"),
+            Err(ParseError::Expected {
+                expected: "`]`",
+                position: 6
+            })
+        );
+        assert_eq!(parse("0x"), Err(ParseError::InvalidNumber { position: 0 }));
+        assert_eq!(
+            parse("12ab"),
+            Err(ParseError::InvalidNumber { position: 0 })
+        );
+        assert_eq!(
+            parse("1 < 2"),
+            Err(ParseError::UnexpectedCharacter {
+                character: '<',
+                position: 2
+            })
+        );
+        assert_eq!(
+            parse("1 2"),
+            Err(ParseError::Expected {
+                expected: "an operator",
+                position: 2
+            })
+        );
+        assert_eq!(
+            parse("1 +"),
+            Err(ParseError::Expected {
+                expected: "a number, symbol or bracket",
+                position: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    ops::Range,
+};
+
+use egui::{text::LayoutJob, Color32, TextFormat, Ui};
+use regex::{Regex, RegexBuilder};
+
+use super::{Message, MessageType};
+
+const HIGHLIGHT_COLOR: Color32 = Color32::from_rgb(112, 92, 16);
+
+/// Decides which console messages are shown, and highlights search matches in them.
+pub struct Filter {
+    show_command: bool,
+    show_info: bool,
+    show_error: bool,
+    show_misc: bool,
+    search: String,
+    use_regex: bool,
+    /// The compiled search; `None` when the search is empty.
+    pattern: Option<Result<Regex, regex::Error>>,
+    hidden_channels: HashSet<String>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self {
+            show_command: true,
+            show_info: true,
+            show_error: true,
+            show_misc: true,
+            search: String::new(),
+            use_regex: false,
+            pattern: None,
+            hidden_channels: HashSet::new(),
+        }
+    }
+
+    /// Plain searches are case-insensitive; regex searches are used as written.
+    fn compile(&mut self) {
+        self.pattern = match (self.search.is_empty(), self.use_regex) {
+            (true, _) => None,
+            (false, true) => Some(Regex::new(&self.search)),
+            (false, false) => Some(
+                RegexBuilder::new(&regex::escape(&self.search))
+                    .case_insensitive(true)
+                    .build(),
+            ),
+        };
+    }
+
+    fn pattern(&self) -> Option<&Regex> {
+        self.pattern.as_ref().and_then(|p| p.as_ref().ok())
+    }
+
+    pub fn matches(&self, message: &Message) -> bool {
+        let shown = match message.message_type {
+            MessageType::Command => self.show_command,
+            MessageType::Info => self.show_info,
+            MessageType::Error => self.show_error,
+            MessageType::Misc(_) => self.show_misc,
+        };
+
+        shown
+            && !self.hidden_channels.contains(&message.channel)
+            && self.pattern().is_none_or(|p| p.is_match(&message.text))
+    }
+
+    /// Returns the byte ranges of `text` that match the search, skipping empty matches.
+    fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        self.pattern()
+            .map(|p| {
+                p.find_iter(text)
+                    .map(|m| m.range())
+                    .filter(|r| !r.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Lays out `text` in `color`, with any search matches highlighted.
+    pub fn layout(&self, text: &str, color: Color32) -> LayoutJob {
+        self.layout_segments(&[(text, color)])
+    }
+
+    /// Lays out each piece of text in its colour, with any search matches across them
+    /// highlighted.
+    pub fn layout_segments(&self, segments: &[(&str, Color32)]) -> LayoutJob {
+        let text: String = segments.iter().map(|(text, _)| *text).collect();
+        let highlights = self.highlights(&text);
+
+        let mut job = LayoutJob::default();
+        let mut start = 0;
+        for (segment, color) in segments {
+            let end = start + segment.len();
+            let normal = TextFormat {
+                color: *color,
+                ..Default::default()
+            };
+            let highlighted = TextFormat {
+                background: HIGHLIGHT_COLOR,
+                ..normal.clone()
+            };
+
+            let mut position = start;
+            for range in &highlights {
+                if range.end <= position || range.start >= end {
+                    continue;
+                }
+                let (from, to) = (range.start.max(position), range.end.min(end));
+                job.append(&text[position..from], 0.0, normal.clone());
+                job.append(&text[from..to], 0.0, highlighted.clone());
+                position = to;
+            }
+            job.append(&text[position..end], 0.0, normal);
+            start = end;
+        }
+        job
+    }
+
+    /// Draws the message type toggles, the search box and a tab for each of `channels`.
+    pub fn ui(&mut self, ui: &mut Ui, channels: &BTreeSet<String>) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_info, "Info");
+            ui.checkbox(&mut self.show_error, "Error");
+            ui.checkbox(&mut self.show_command, "Command");
+            ui.checkbox(&mut self.show_misc, "Misc");
+            ui.separator();
+
+            let search_changed = ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.search)
+                        .hint_text("Search")
+                        .desired_width(200.0),
+                )
+                .changed();
+            let regex_changed = ui.checkbox(&mut self.use_regex, "Regex").changed();
+            if search_changed || regex_changed {
+                self.compile();
+            }
+
+            if let Some(Err(err)) = &self.pattern {
+                ui.colored_label(Color32::RED, "Invalid regex")
+                    .on_hover_text(err.to_string());
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            if ui
+                .selectable_label(self.hidden_channels.is_empty(), "All")
+                .clicked()
+            {
+                self.hidden_channels.clear();
+            }
+
+            for channel in channels {
+                let shown = !self.hidden_channels.contains(channel);
+                if ui.selectable_label(shown, channel).clicked() {
+                    if shown {
+                        self.hidden_channels.insert(channel.clone());
+                    } else {
+                        self.hidden_channels.remove(channel);
+                    }
+                }
+            }
+        });
+    }
+}
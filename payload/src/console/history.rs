@@ -0,0 +1,95 @@
+use std::{collections::VecDeque, fs, path::PathBuf};
+
+const HISTORY_CAPACITY: usize = 200;
+pub const HISTORY_FILENAME: &str = "console_history.txt";
+
+/// Previously entered command lines, most recent last.
+pub struct History {
+    entries: VecDeque<String>,
+    /// The entry currently being shown while walking through the history.
+    cursor: Option<usize>,
+    /// The line that was being typed before walking through the history.
+    draft: String,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(HISTORY_CAPACITY),
+            cursor: None,
+            draft: String::new(),
+            path,
+        }
+    }
+
+    pub fn load(&mut self) -> anyhow::Result<()> {
+        let path = match &self.path {
+            Some(path) if path.exists() => path,
+            _ => return Ok(()),
+        };
+
+        for line in fs::read_to_string(path)?.lines() {
+            self.push_entry(line);
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            let mut contents = String::new();
+            for entry in &self.entries {
+                contents.push_str(entry);
+                contents.push('\n');
+            }
+            fs::write(path, contents)?;
+        }
+        Ok(())
+    }
+
+    /// Records an entered line, skipping it if it repeats the previous entry.
+    pub fn push(&mut self, line: &str) {
+        self.cursor = None;
+        self.draft.clear();
+        self.push_entry(line);
+    }
+
+    fn push_entry(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.entries.back().map(|s| s.as_str()) == Some(line) {
+            return;
+        }
+
+        if self.entries.len() == HISTORY_CAPACITY {
+            let _ = self.entries.pop_front();
+        }
+        self.entries.push_back(line.to_owned());
+    }
+
+    /// Steps back to an older entry, remembering `current` so that it can be restored.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let index = match self.cursor {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_owned();
+                self.entries.len() - 1
+            }
+            Some(index) => index.saturating_sub(1),
+        };
+
+        self.cursor = Some(index);
+        self.entries.get(index).map(|s| s.as_str())
+    }
+
+    /// Steps forward to a newer entry, returning the original draft after the newest one.
+    pub fn next(&mut self) -> Option<&str> {
+        let index = self.cursor?;
+        if index + 1 < self.entries.len() {
+            self.cursor = Some(index + 1);
+            self.entries.get(index + 1).map(|s| s.as_str())
+        } else {
+            self.cursor = None;
+            Some(&self.draft)
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    fs::{self, File},
+    io::{LineWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+
+use super::{Cvar, CvarRegistry, Message};
+
+const LOG_FILE_STEM: &str = "console";
+
+/// Mirrors console messages to `console.log`, moving the previous log to `console.1.log`
+/// (and so on) at the start of each session and whenever it grows too large.
+pub struct LogFile {
+    writer: Option<LineWriter<File>>,
+    /// The directory a relative `log_directory` is in, or `None` if it is not known.
+    base: Option<PathBuf>,
+    directory: PathBuf,
+    written: u64,
+    /// The `log_directory` that could not be written to, so that the failure is only
+    /// reported once.
+    failed: Option<String>,
+}
+
+impl LogFile {
+    pub fn new(base: Option<PathBuf>) -> Self {
+        Self {
+            writer: None,
+            base,
+            directory: PathBuf::new(),
+            written: 0,
+            failed: None,
+        }
+    }
+
+    pub fn write(&mut self, cvars: &CvarRegistry, message: &Message) -> anyhow::Result<()> {
+        self.write_line(cvars, message, &message.time, &message.text)
+    }
+
+    /// Notes how many times `message` was repeated, since repeats are not written as they
+    /// arrive.
+    pub fn write_repeats(&mut self, cvars: &CvarRegistry, message: &Message) -> anyhow::Result<()> {
+        let text = format!("(last message repeated {} times)", message.repeats);
+        self.write_line(cvars, message, &message.last_time, &text)
+    }
+
+    fn write_line(
+        &mut self,
+        cvars: &CvarRegistry,
+        message: &Message,
+        time: &DateTime<Local>,
+        text: &str,
+    ) -> anyhow::Result<()> {
+        if !cvars.bool("log_file").unwrap_or(false) {
+            self.writer = None;
+            return Ok(());
+        }
+
+        let setting = match cvars.string("log_directory") {
+            Some(setting) => setting,
+            None => return Ok(()),
+        };
+        if self.failed.as_deref() == Some(setting) {
+            return Ok(());
+        }
+
+        let line = format!(
+            "{} [{}] [{}] {}\n",
+            time.format("%Y-%m-%d %H:%M:%S%.3f"),
+            message.message_type.name(),
+            message.channel,
+            text
+        );
+        let result = self
+            .resolve(Path::new(setting))
+            .and_then(|directory| self.write_to(cvars, &directory, &line));
+        if result.is_err() {
+            self.writer = None;
+            self.failed = Some(setting.to_owned());
+        }
+        result
+    }
+
+    fn resolve(&self, directory: &Path) -> anyhow::Result<PathBuf> {
+        match &self.base {
+            _ if directory.is_absolute() => Ok(directory.to_path_buf()),
+            Some(base) => Ok(base.join(directory)),
+            None => anyhow::bail!(
+                "`{}` is relative, but the payload's directory is not known.",
+                directory.display()
+            ),
+        }
+    }
+
+    fn write_to(
+        &mut self,
+        cvars: &CvarRegistry,
+        directory: &Path,
+        line: &str,
+    ) -> anyhow::Result<()> {
+        let max_size = cvars.int("log_max_size").unwrap_or(1024) as u64 * 1024;
+        let kept = cvars.int("log_files_kept").unwrap_or(0) as usize;
+
+        let writer = match &mut self.writer {
+            Some(writer) if self.directory == directory && self.written < max_size => writer,
+            _ => {
+                self.writer = None;
+                rotate(directory, kept)?;
+                self.directory = directory.to_path_buf();
+                self.written = 0;
+                self.writer
+                    .insert(LineWriter::new(File::create(log_path(directory, 0))?))
+            }
+        };
+
+        writer.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn log_path(directory: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => directory.join(format!("{}.log", LOG_FILE_STEM)),
+        index => directory.join(format!("{}.{}.log", LOG_FILE_STEM, index)),
+    }
+}
+
+/// Shifts each log in `directory` up by one, dropping any beyond the `kept` old logs.
+fn rotate(directory: &Path, kept: usize) -> anyhow::Result<()> {
+    fs::create_dir_all(directory)?;
+    let oldest = log_path(directory, kept);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+
+    for index in (0..kept).rev() {
+        let path = log_path(directory, index);
+        if path.exists() {
+            fs::rename(path, log_path(directory, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn register_cvars(cvars: &mut CvarRegistry) -> anyhow::Result<()> {
+    cvars.register(Cvar::new(
+        "log_file",
+        "Whether console messages are also written to a log file.",
+        true,
+    ))?;
+    cvars.register(Cvar::new(
+        "log_directory",
+        "The directory for log files, relative to payload.dll.",
+        "logs",
+    ))?;
+    cvars.register(
+        Cvar::new(
+            "log_max_size",
+            "The size in KiB at which the log file is rotated.",
+            1024_i64,
+        )
+        .range(1.0, 1024.0 * 1024.0),
+    )?;
+    cvars.register(
+        Cvar::new("log_files_kept", "How many old log files to keep.", 5_i64).range(0.0, 100.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::{output::Content, MessageType};
+
+    fn message(text: &str) -> Message {
+        let time = Local::now();
+        Message {
+            time,
+            text: text.to_owned(),
+            content: Content::Text,
+            collapsed: true,
+            message_type: MessageType::Info,
+            channel: "test".to_owned(),
+            repeats: 1,
+            last_time: time,
+        }
+    }
+
+    fn cvars(directory: &str) -> CvarRegistry {
+        let mut cvars = CvarRegistry::new(None);
+        register_cvars(&mut cvars).unwrap();
+        cvars.set("log_directory", directory).unwrap();
+        cvars
+    }
+
+    #[test]
+    fn reports_unknown_base_directory_once() {
+        let cvars = cvars("logs");
+        let mut log_file = LogFile::new(None);
+        assert!(log_file.write(&cvars, &message("a")).is_err());
+        assert!(log_file.write(&cvars, &message("b")).is_ok());
+    }
+
+    #[test]
+    fn writes_and_rotates_logs() {
+        let directory = std::env::temp_dir().join(format!("console-log-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let cvars = cvars(directory.to_str().unwrap());
+
+        LogFile::new(None).write(&cvars, &message("first")).unwrap();
+        let mut log_file = LogFile::new(None);
+        log_file.write(&cvars, &message("second")).unwrap();
+        let mut repeated = message("second");
+        repeated.repeats = 3;
+        log_file.write_repeats(&cvars, &repeated).unwrap();
+        drop(log_file);
+
+        let current = fs::read_to_string(log_path(&directory, 0)).unwrap();
+        assert!(current.contains("[Info] [test] second\n"));
+        assert!(current.contains("[Info] [test] (last message repeated 3 times)\n"));
+        let previous = fs::read_to_string(log_path(&directory, 1)).unwrap();
+        assert!(previous.contains("[Info] [test] first\n"));
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,252 @@
+//! Inline colours in console messages. Three forms are understood:
+//!
+//! - ANSI SGR escapes, such as `\x1b[31m`, including 256-colour and RGB colours. Other
+//!   escape sequences are removed.
+//! - `^0` to `^9`, which pick from the Quake palette: black, red, green, yellow, blue,
+//!   cyan, magenta, white, orange and grey.
+//! - `{red}` and the other palette names, `{#rrggbb}`, and `{reset}` to go back to the
+//!   message's own colour. Braces around anything else are left alone.
+
+use super::Color;
+
+/// A run of text in one colour. `None` is the message's own colour.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub text: String,
+    pub color: Option<Color>,
+}
+
+const CARET_COLORS: [Color; 10] = [
+    (0, 0, 0),
+    (241, 76, 76),
+    (35, 209, 139),
+    (245, 245, 67),
+    (59, 142, 234),
+    (41, 184, 219),
+    (214, 112, 214),
+    (255, 255, 255),
+    (255, 165, 0),
+    (128, 128, 128),
+];
+
+const NAMED_COLORS: [(&str, Color); 11] = [
+    ("black", CARET_COLORS[0]),
+    ("red", CARET_COLORS[1]),
+    ("green", CARET_COLORS[2]),
+    ("yellow", CARET_COLORS[3]),
+    ("blue", CARET_COLORS[4]),
+    ("cyan", CARET_COLORS[5]),
+    ("magenta", CARET_COLORS[6]),
+    ("white", CARET_COLORS[7]),
+    ("orange", CARET_COLORS[8]),
+    ("gray", CARET_COLORS[9]),
+    ("grey", CARET_COLORS[9]),
+];
+
+/// The 16 basic ANSI colours, normal then bright.
+const ANSI_COLORS: [Color; 16] = [
+    (0, 0, 0),
+    (205, 49, 49),
+    (13, 188, 121),
+    (229, 229, 16),
+    (36, 114, 200),
+    (188, 63, 188),
+    (17, 168, 205),
+    (229, 229, 229),
+    (102, 102, 102),
+    (241, 76, 76),
+    (35, 209, 139),
+    (245, 245, 67),
+    (59, 142, 234),
+    (214, 112, 214),
+    (41, 184, 219),
+    (255, 255, 255),
+];
+
+/// Returns whether `text` might contain markup, without parsing it.
+pub fn may_contain_markup(text: &str) -> bool {
+    text.contains(['\x1b', '^', '{'])
+}
+
+/// Splits `text` into coloured segments, removing the markup.
+pub fn parse(text: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut current = Segment {
+        text: String::new(),
+        color: None,
+    };
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match parse_code(rest, current.color) {
+            Some((color, len)) => {
+                if color != current.color {
+                    let next = Segment {
+                        text: String::new(),
+                        color,
+                    };
+                    let previous = std::mem::replace(&mut current, next);
+                    if !previous.text.is_empty() {
+                        segments.push(previous);
+                    }
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                current.text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !current.text.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+/// Returns `text` without its markup, for log files and standard output.
+pub fn strip(text: &str) -> String {
+    if !may_contain_markup(text) {
+        return text.to_owned();
+    }
+    parse(text).into_iter().map(|s| s.text).collect()
+}
+
+/// If `text` starts with markup, returns the colour it switches to from `color` and the
+/// length of the markup in bytes.
+fn parse_code(text: &str, color: Option<Color>) -> Option<(Option<Color>, usize)> {
+    let bytes = text.as_bytes();
+    match bytes.first()? {
+        b'^' => {
+            let digit = (*bytes.get(1)? as char).to_digit(10)?;
+            Some((Some(CARET_COLORS[digit as usize]), 2))
+        }
+        b'{' => {
+            let end = text.find('}')?;
+            Some((named_color(&text[1..end])?, end + 1))
+        }
+        b'\x1b' => {
+            if bytes.get(1) != Some(&b'[') {
+                return None;
+            }
+            // Control sequences end with a byte from '@' to '~'.
+            let end = 2 + bytes[2..].iter().position(|b| (b'@'..=b'~').contains(b))?;
+            match bytes[end] {
+                b'm' => Some((sgr_color(&text[2..end], color), end + 1)),
+                _ => Some((color, end + 1)),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the colour for a `{name}`, where `Some(None)` resets the colour.
+fn named_color(name: &str) -> Option<Option<Color>> {
+    if name == "reset" {
+        return Some(None);
+    }
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Some((channel(0)?, channel(2)?, channel(4)?)));
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(named, _)| *named == name)
+        .map(|(_, color)| Some(*color))
+}
+
+/// Applies the parameters of an SGR escape to `color`, ignoring anything but colours.
+fn sgr_color(params: &str, mut color: Option<Color>) -> Option<Color> {
+    let mut params = params.split(';').map(|p| p.parse::<u8>().unwrap_or(0));
+    while let Some(param) = params.next() {
+        match param {
+            0 | 39 => color = None,
+            30..=37 => color = Some(ANSI_COLORS[(param - 30) as usize]),
+            90..=97 => color = Some(ANSI_COLORS[(param - 90 + 8) as usize]),
+            38 => match params.next() {
+                Some(5) => color = params.next().map(color_256),
+                Some(2) => {
+                    if let (Some(r), Some(g), Some(b)) =
+                        (params.next(), params.next(), params.next())
+                    {
+                        color = Some((r, g, b));
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    color
+}
+
+/// Returns a colour from the xterm 256-colour palette.
+fn color_256(index: u8) -> Color {
+    match index {
+        0..=15 => ANSI_COLORS[index as usize],
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, color: Option<Color>) -> Segment {
+        Segment {
+            text: text.to_owned(),
+            color,
+        }
+    }
+
+    #[test]
+    fn plain_text_is_one_segment() {
+        assert_eq!(
+            parse("hello {world} ^x"),
+            [segment("hello {world} ^x", None)]
+        );
+    }
+
+    #[test]
+    fn caret_and_named_colours() {
+        assert_eq!(
+            parse("hp ^1low{reset} now {#00ff80}ok"),
+            [
+                segment("hp ", None),
+                segment("low", Some(CARET_COLORS[1])),
+                segment(" now ", None),
+                segment("ok", Some((0, 255, 128))),
+            ]
+        );
+    }
+
+    #[test]
+    fn ansi_escapes() {
+        assert_eq!(
+            parse("\x1b[1;31merror\x1b[0m: \x1b[38;2;1;2;3mrgb\x1b[K"),
+            [
+                segment("error", Some(ANSI_COLORS[1])),
+                segment(": ", None),
+                segment("rgb", Some((1, 2, 3))),
+            ]
+        );
+    }
+
+    #[test]
+    fn strip_removes_markup() {
+        assert_eq!(strip("^2ok{reset} \x1b[32mgreen\x1b[0m"), "ok green");
+        assert_eq!(strip("{\"json\": 1}"), "{\"json\": 1}");
+    }
+}
@@ -0,0 +1,1199 @@
+mod alias;
+mod command;
+mod completion;
+mod cvar;
+mod expr;
+mod filter;
+mod history;
+mod log_file;
+mod markup;
+mod output;
+mod rate_limit;
+mod script;
+mod sender;
+mod tokenizer;
+
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use chrono::{DateTime, Local};
+use egui::{
+    epaint::text::cursor::CCursor,
+    text_edit::{CCursorRange, TextEditState},
+    CtxRef, RichText, Ui,
+};
+use lazy_static::lazy_static;
+
+pub use self::{
+    command::{ArgType, Args, CommandSignature, Value},
+    cvar::{Cvar, CvarRegistry, CvarValue},
+    expr::Environment,
+    markup::strip as strip_markup,
+    output::{Link, Span, Table, COMMAND_HANDLER},
+    sender::ConsoleSender,
+    tokenizer::{quote, tokenize},
+};
+use self::{
+    completion::Completion,
+    cvar::CVARS_FILENAME,
+    filter::Filter,
+    history::{History, HISTORY_FILENAME},
+    log_file::LogFile,
+    output::Content,
+    rate_limit::RateLimiter,
+    sender::Event,
+};
+
+// I hope you're happy, Josh, you're making me sell out my countrymen
+type Color = (u8, u8, u8);
+type Command = Box<dyn FnMut(&mut Console, &str, &[&str]) -> anyhow::Result<()> + Send>;
+type TypedCommand = Box<dyn FnMut(&mut Console, &Args) -> anyhow::Result<()> + Send>;
+/// Returns the possible values for the argument following `args`.
+type Completer = Box<dyn Fn(&Console, &[&str]) -> Vec<String> + Send>;
+/// Called with the argument of a clicked [`Link`].
+type LinkHandler = Box<dyn FnMut(&mut Console, &str) -> anyhow::Result<()> + Send>;
+#[derive(PartialEq)]
+pub enum MessageType {
+    Command,
+    Info,
+    Error,
+    Misc(Color),
+}
+
+impl MessageType {
+    fn name(&self) -> &'static str {
+        match self {
+            MessageType::Command => "Command",
+            MessageType::Info => "Info",
+            MessageType::Error => "Error",
+            MessageType::Misc(_) => "Misc",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            MessageType::Command => (243, 145, 137),
+            MessageType::Info => (255, 255, 255),
+            MessageType::Error => (249, 7, 22),
+            MessageType::Misc(color) => *color,
+        }
+    }
+}
+const TIME_COLOR: Color = (255, 242, 204);
+const MAX_QUEUED_COMMANDS: usize = 10000;
+/// Stops an event that keeps sending itself from stalling the frame.
+const MAX_EVENTS_PER_FRAME: usize = 10000;
+/// The channel for messages that are not logged to a specific one.
+pub const DEFAULT_CHANNEL: &str = "console";
+
+pub struct Message {
+    time: DateTime<Local>,
+    /// The message as plain text, however it is shown.
+    text: String,
+    content: Content,
+    /// Whether a block's lines are hidden.
+    collapsed: bool,
+    message_type: MessageType,
+    channel: String,
+    /// How many times the message was pushed in a row, counting the first.
+    repeats: u32,
+    /// When the message was last pushed.
+    last_time: DateTime<Local>,
+}
+
+impl Message {
+    pub fn time(&self) -> &DateTime<Local> {
+        &self.time
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn message_type(&self) -> &MessageType {
+        &self.message_type
+    }
+
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    pub fn repeats(&self) -> u32 {
+        self.repeats
+    }
+
+    /// Whether pushing `text` would repeat this message rather than add a new one.
+    fn is_repeated_by(&self, channel: &str, text: &str, message_type: &MessageType) -> bool {
+        let source = match &self.content {
+            Content::Text => &self.text,
+            Content::Markup { source, .. } => source,
+            _ => return false,
+        };
+        source == text && self.channel == channel && self.message_type == *message_type
+    }
+}
+
+enum Callback {
+    Raw(Command),
+    Typed(TypedCommand),
+}
+
+/// A line of the console's output, which is a whole message or part of a table or block.
+#[derive(Clone, Copy)]
+enum DisplayRow {
+    Message(usize),
+    TableRow(usize, usize),
+    BlockLine(usize, usize),
+}
+
+/// Identifies a registered command so that it can be removed again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommandHandle(u64);
+
+/// A registered command, as listed by [`Console::command_summaries`].
+pub struct CommandSummary {
+    pub name: String,
+    pub description: String,
+    pub usage: String,
+    pub requires_arguments: bool,
+}
+
+struct RegisteredCommand {
+    handle: CommandHandle,
+    signature: Option<CommandSignature>,
+    /// Taken out of the registry while the command is running.
+    callback: Option<Callback>,
+    completer: Option<Completer>,
+}
+
+struct QueuedCommand {
+    source: Option<String>,
+    words: Vec<String>,
+}
+
+pub struct Console {
+    text_input: String,
+    /// Set to focus the command line the next time it is drawn.
+    focus_input: bool,
+    history: History,
+    completion: Option<Completion>,
+    messages: VecDeque<Message>,
+    /// How many messages have ever been pushed, including those no longer kept.
+    message_count: u64,
+    channels: BTreeSet<String>,
+    filter: Filter,
+    log_file: LogFile,
+    rate_limiter: RateLimiter,
+    /// Resolves the symbols and memory reads in expressions.
+    environment: Box<dyn Environment + Send>,
+    commands: HashMap<String, RegisteredCommand>,
+    /// Taken out of the map while they are running, like commands.
+    link_handlers: HashMap<String, Option<LinkHandler>>,
+    next_command_handle: u64,
+    cvars: CvarRegistry,
+    queue: VecDeque<QueuedCommand>,
+    running_queue: bool,
+    sender: ConsoleSender,
+    receiver: Receiver<Event>,
+}
+
+impl Console {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::in_directory(crate::paths::payload_directory().ok())
+    }
+
+    /// Creates a console that keeps its history, variables and logs in `directory`, or
+    /// nowhere if it is `None`.
+    pub fn in_directory(directory: Option<PathBuf>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self::with_channel(ConsoleSender(sender), receiver, directory)
+    }
+
+    fn with_channel(
+        sender: ConsoleSender,
+        receiver: Receiver<Event>,
+        directory: Option<PathBuf>,
+    ) -> Self {
+        let file = |filename| directory.as_ref().map(|directory| directory.join(filename));
+        let mut console = Self {
+            text_input: Default::default(),
+            focus_input: false,
+            history: History::new(file(HISTORY_FILENAME)),
+            completion: None,
+            messages: VecDeque::with_capacity(1000),
+            message_count: 0,
+            channels: BTreeSet::new(),
+            filter: Filter::new(),
+            log_file: LogFile::new(directory.clone()),
+            rate_limiter: RateLimiter::new(),
+            environment: Box::new(expr::NoEnvironment),
+            commands: HashMap::new(),
+            link_handlers: HashMap::new(),
+            next_command_handle: 0,
+            cvars: CvarRegistry::new(file(CVARS_FILENAME)),
+            queue: VecDeque::new(),
+            running_queue: false,
+            sender,
+            receiver,
+        };
+
+        // Registered and loaded first so that the log file settings apply to every message.
+        if let Err(err) = log_file::register_cvars(&mut console.cvars) {
+            console.push_back_error(format!("Failed to register log file variables: {}", err));
+        }
+        if let Err(err) = rate_limit::register_cvars(&mut console.cvars) {
+            console.push_back_error(format!("Failed to register rate limit variables: {}", err));
+        }
+
+        if let Err(err) = console.cvars.load() {
+            console.push_back_error(format!("Failed to load console variables: {}", err));
+        }
+
+        if let Err(err) = console.history.load() {
+            console.push_back_error(format!("Failed to load console history: {}", err));
+        }
+
+        console.register_command(
+            CommandSignature::new("echo", "Prints its arguments.").rest(
+                "text",
+                ArgType::String,
+                "The text to print.",
+            ),
+            |console, args| {
+                let text: Vec<_> = args.rest().iter().map(|v| v.to_string()).collect();
+                console.push_back_message(text.join(" "), MessageType::Info);
+                Ok(())
+            },
+        );
+
+        console.register_command_with_completer(
+            CommandSignature::new("help", "Lists every command, or describes one.").optional_arg(
+                "command",
+                ArgType::String,
+                "",
+                "The command to describe.",
+            ),
+            |console, args| console.help(args.string("command")?),
+            |console, args| match args {
+                [] => console.commands.keys().cloned().collect(),
+                _ => vec![],
+            },
+        );
+
+        console.add_link_handler(COMMAND_HANDLER, |console, line| {
+            console.submit(line);
+            Ok(())
+        });
+
+        cvar::register_commands(&mut console);
+        script::register_commands(&mut console);
+        alias::register_commands(&mut console);
+        expr::register_commands(&mut console);
+
+        console
+    }
+
+    pub fn add_command(
+        &mut self,
+        cmd: &str,
+        command: impl FnMut(&mut Console, &str, &[&str]) -> anyhow::Result<()> + Send + 'static,
+    ) -> CommandHandle {
+        self.insert_command(cmd, None, Callback::Raw(Box::new(command)), None)
+    }
+
+    pub fn add_command_with_completer(
+        &mut self,
+        cmd: &str,
+        command: impl FnMut(&mut Console, &str, &[&str]) -> anyhow::Result<()> + Send + 'static,
+        completer: impl Fn(&Console, &[&str]) -> Vec<String> + Send + 'static,
+    ) -> CommandHandle {
+        self.insert_command(
+            cmd,
+            None,
+            Callback::Raw(Box::new(command)),
+            Some(Box::new(completer)),
+        )
+    }
+
+    /// Registers a command whose arguments are validated and converted according to
+    /// `signature` before it is called.
+    pub fn register_command(
+        &mut self,
+        signature: CommandSignature,
+        command: impl FnMut(&mut Console, &Args) -> anyhow::Result<()> + Send + 'static,
+    ) -> CommandHandle {
+        let name = signature.name().to_owned();
+        self.insert_command(
+            &name,
+            Some(signature),
+            Callback::Typed(Box::new(command)),
+            None,
+        )
+    }
+
+    pub fn register_command_with_completer(
+        &mut self,
+        signature: CommandSignature,
+        command: impl FnMut(&mut Console, &Args) -> anyhow::Result<()> + Send + 'static,
+        completer: impl Fn(&Console, &[&str]) -> Vec<String> + Send + 'static,
+    ) -> CommandHandle {
+        let name = signature.name().to_owned();
+        self.insert_command(
+            &name,
+            Some(signature),
+            Callback::Typed(Box::new(command)),
+            Some(Box::new(completer)),
+        )
+    }
+
+    fn insert_command(
+        &mut self,
+        cmd: &str,
+        signature: Option<CommandSignature>,
+        callback: Callback,
+        completer: Option<Completer>,
+    ) -> CommandHandle {
+        let handle = CommandHandle(self.next_command_handle);
+        self.next_command_handle += 1;
+
+        self.commands.insert(
+            cmd.to_owned(),
+            RegisteredCommand {
+                handle,
+                signature,
+                callback: Some(callback),
+                completer,
+            },
+        );
+        handle
+    }
+
+    /// Removes a command, unless it has since been replaced by another command with the
+    /// same name. Returns whether the command was removed.
+    pub fn remove_command(&mut self, handle: CommandHandle) -> bool {
+        let len = self.commands.len();
+        self.commands.retain(|_, command| command.handle != handle);
+        self.commands.len() != len
+    }
+
+    /// Registers `handler` to be called with the argument of any clicked [`Link`] that
+    /// names it.
+    pub fn add_link_handler(
+        &mut self,
+        name: &str,
+        handler: impl FnMut(&mut Console, &str) -> anyhow::Result<()> + Send + 'static,
+    ) {
+        self.link_handlers
+            .insert(name.to_owned(), Some(Box::new(handler)));
+    }
+
+    fn follow_link(&mut self, link: Link) {
+        let mut handler = match self.link_handlers.get_mut(&link.handler) {
+            Some(handler) => match handler.take() {
+                Some(handler) => handler,
+                None => return,
+            },
+            None => {
+                self.push_back_error(format!("No link handler named `{}`.", link.handler));
+                return;
+            }
+        };
+
+        let result = handler(self, &link.argument);
+        if let Some(slot @ None) = self.link_handlers.get_mut(&link.handler) {
+            *slot = Some(handler);
+        }
+        if let Err(err) = result {
+            self.push_back_error(err.to_string());
+        }
+    }
+
+    /// Returns every registered command, sorted by name.
+    pub fn command_summaries(&self) -> Vec<CommandSummary> {
+        let mut summaries: Vec<_> = self
+            .commands
+            .iter()
+            .map(|(name, command)| match &command.signature {
+                Some(signature) => CommandSummary {
+                    name: name.clone(),
+                    description: signature.description().to_owned(),
+                    usage: signature.usage(),
+                    requires_arguments: signature.has_required_args(),
+                },
+                None => CommandSummary {
+                    name: name.clone(),
+                    description: String::new(),
+                    usage: name.clone(),
+                    requires_arguments: false,
+                },
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        summaries
+    }
+
+    /// Replaces the text of the command line and focuses it, so that the user can finish
+    /// the command.
+    pub fn set_input(&mut self, text: String) {
+        self.text_input = text;
+        self.completion = None;
+        self.focus_input = true;
+    }
+
+    pub fn cvars(&self) -> &CvarRegistry {
+        &self.cvars
+    }
+
+    pub fn cvars_mut(&mut self) -> &mut CvarRegistry {
+        &mut self.cvars
+    }
+
+    pub fn push_back_message(&mut self, message: String, message_type: MessageType) {
+        self.push_back_channel_message(DEFAULT_CHANNEL, message, message_type);
+    }
+
+    /// Logs `message` to `channel`, which gets its own tab in the console view.
+    pub fn push_back_channel_message(
+        &mut self,
+        channel: &str,
+        message: String,
+        message_type: MessageType,
+    ) {
+        self.push_back_content(Local::now(), channel, message, Content::Text, message_type);
+    }
+
+    /// Pushes a message made of spans, some of which may be links.
+    pub fn push_back_spans(&mut self, spans: Vec<Span>, message_type: MessageType) {
+        let content = Content::Spans(spans);
+        let text = content.plain_text().unwrap_or_default();
+        self.push_back_content(Local::now(), DEFAULT_CHANNEL, text, content, message_type);
+    }
+
+    pub fn push_back_table(&mut self, table: Table) {
+        let content = Content::Table(table);
+        let text = content.plain_text().unwrap_or_default();
+        self.push_back_content(
+            Local::now(),
+            DEFAULT_CHANNEL,
+            text,
+            content,
+            MessageType::Info,
+        );
+    }
+
+    /// Pushes `title` with `lines` beneath it, which are hidden until the title is clicked.
+    pub fn push_back_block(
+        &mut self,
+        title: String,
+        lines: Vec<String>,
+        message_type: MessageType,
+    ) {
+        let content = Content::Block { title, lines };
+        let text = content.plain_text().unwrap_or_default();
+        self.push_back_content(Local::now(), DEFAULT_CHANNEL, text, content, message_type);
+    }
+
+    /// Pushes a message, or counts it as a repeat of the last one if it has the same text.
+    /// Colour markup in text messages is parsed here, leaving the plain text for the log.
+    fn push_back_content(
+        &mut self,
+        time: DateTime<Local>,
+        channel: &str,
+        mut message: String,
+        mut content: Content,
+        message_type: MessageType,
+    ) {
+        let mut logged = Ok(());
+        if let Some(last) = self.messages.back_mut() {
+            if matches!(content, Content::Text)
+                && last.is_repeated_by(channel, &message, &message_type)
+            {
+                last.repeats += 1;
+                last.last_time = time;
+                return;
+            }
+
+            if last.repeats > 1 {
+                logged = self.log_file.write_repeats(&self.cvars, last);
+            }
+        }
+
+        if matches!(content, Content::Text) && markup::may_contain_markup(&message) {
+            let segments = markup::parse(&message);
+            let text: String = segments.iter().map(|s| s.text.as_str()).collect();
+            if text != message {
+                content = Content::Markup {
+                    source: std::mem::replace(&mut message, text),
+                    segments,
+                };
+            }
+        }
+
+        self.append(time, channel, message, content, message_type);
+        let logged = logged.and_then(|_| match self.messages.back() {
+            Some(message) => self.log_file.write(&self.cvars, message),
+            None => Ok(()),
+        });
+
+        // The error is not itself logged, since the log is what failed.
+        if let Err(err) = logged {
+            self.append(
+                Local::now(),
+                DEFAULT_CHANNEL,
+                format!("Failed to write to the log file: {}", err),
+                Content::Text,
+                MessageType::Error,
+            );
+        }
+    }
+
+    /// Adds a message after the others, dropping the oldest if there is no room.
+    fn append(
+        &mut self,
+        time: DateTime<Local>,
+        channel: &str,
+        text: String,
+        content: Content,
+        message_type: MessageType,
+    ) {
+        if self.messages.len() == self.messages.capacity() {
+            let _ = self.messages.pop_front();
+        }
+
+        if !self.channels.contains(channel) {
+            self.channels.insert(channel.to_owned());
+        }
+        self.message_count += 1;
+        self.messages.push_back(Message {
+            time,
+            text,
+            content,
+            collapsed: true,
+            message_type,
+            channel: channel.to_owned(),
+            repeats: 1,
+            last_time: time,
+        });
+    }
+
+    pub fn push_back_info(&mut self, message: String) {
+        self.push_back_message(message, MessageType::Info);
+    }
+
+    pub fn push_back_error(&mut self, message: String) {
+        self.push_back_message(message, MessageType::Error);
+    }
+
+    /// The number of messages pushed so far, for use with [`Console::messages_since`].
+    pub fn message_count(&self) -> u64 {
+        self.message_count
+    }
+
+    /// Returns the messages pushed since [`Console::message_count`] returned `count`, minus
+    /// any that have since been dropped to make room. Repeats of the last message are
+    /// counted on it rather than returned again.
+    pub fn messages_since(&self, count: u64) -> impl Iterator<Item = &Message> {
+        let first = self.message_count - self.messages.len() as u64;
+        self.messages
+            .iter()
+            .skip(count.saturating_sub(first) as usize)
+    }
+
+    /// Shows a notice on each channel that had messages dropped by the rate limit.
+    fn report_suppressed(&mut self) {
+        for (channel, count) in self.rate_limiter.take_suppressed(Instant::now()) {
+            self.push_back_channel_message(
+                &channel,
+                format!("{} messages suppressed", count),
+                MessageType::Error,
+            );
+        }
+    }
+
+    /// Sets what the symbols and memory reads in expressions refer to.
+    pub fn set_environment(&mut self, environment: impl Environment + Send + 'static) {
+        self.environment = Box::new(environment);
+    }
+
+    /// Evaluates an expression such as `[HITMAN3.exe+0x1A2B3C] + 8`.
+    pub fn evaluate(&self, text: &str) -> anyhow::Result<u64> {
+        expr::evaluate(text, self.environment.as_ref())
+    }
+
+    /// Returns a handle for logging to and running commands on this console from other
+    /// threads.
+    pub fn sender(&self) -> ConsoleSender {
+        self.sender.clone()
+    }
+
+    /// Applies everything sent through this console's [`ConsoleSender`]s so far. Messages
+    /// are limited to `console_rate_limit` a second per channel, since they usually come
+    /// from logging in hooks that may run every frame.
+    pub fn process_events(&mut self) {
+        self.report_suppressed();
+
+        let limit = self.cvars.int("console_rate_limit").unwrap_or(0).max(0) as u32;
+        for _ in 0..MAX_EVENTS_PER_FRAME {
+            match self.receiver.try_recv() {
+                Ok(Event::Message {
+                    time,
+                    channel,
+                    text,
+                    message_type,
+                }) => {
+                    let repeat = self
+                        .messages
+                        .back()
+                        .is_some_and(|last| last.is_repeated_by(&channel, &text, &message_type));
+                    if repeat || self.rate_limiter.allow(&channel, limit, Instant::now()) {
+                        self.push_back_content(time, &channel, text, Content::Text, message_type);
+                    }
+                }
+                Ok(Event::Execute(line)) => self.execute(&line),
+                Ok(Event::Run(callback)) => callback(self),
+                Err(_) => break,
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &CtxRef) {
+        egui::TopBottomPanel::bottom("console")
+            .resizable(true)
+            .min_height(200.0)
+            .show(ctx, |ui| self.ui(ui));
+    }
+
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.heading("Console");
+        ui.separator();
+
+        egui::TopBottomPanel::bottom("bottom_panel")
+            .resizable(false)
+            .min_height(0.0)
+            .show_inside(ui, |ui| {
+                let input_id = ui.make_persistent_id("console_input");
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.text_input)
+                        .id(input_id)
+                        .hint_text("Enter a command like 'exit' or `pause`")
+                        .desired_width(f32::INFINITY)
+                        .lock_focus(true),
+                );
+
+                if std::mem::take(&mut self.focus_input) {
+                    response.request_focus();
+                    Self::move_cursor_to_end(ui, input_id, &self.text_input);
+                }
+
+                if matches!(&self.completion, Some(c) if c.line() != self.text_input) {
+                    self.completion = None;
+                }
+
+                if response.has_focus() {
+                    let replacement = if ui.input().key_pressed(egui::Key::Tab) {
+                        self.complete()
+                    } else if ui.input().key_pressed(egui::Key::ArrowUp) {
+                        self.history.previous(&self.text_input).map(str::to_owned)
+                    } else if ui.input().key_pressed(egui::Key::ArrowDown) {
+                        self.history.next().map(str::to_owned)
+                    } else {
+                        None
+                    };
+
+                    if let Some(line) = replacement {
+                        self.text_input = line;
+                        Self::move_cursor_to_end(ui, input_id, &self.text_input);
+                    }
+                }
+
+                if self.completion.is_some() {
+                    self.completion_ui(ui, input_id);
+                }
+
+                if response.lost_focus() && ui.input().key_pressed(egui::Key::Enter) {
+                    self.completion = None;
+                    self.enter_command();
+                    response.request_focus();
+                }
+            });
+
+        egui::TopBottomPanel::top("filter_panel")
+            .resizable(false)
+            .min_height(0.0)
+            .show_inside(ui, |ui| self.filter.ui(ui, &self.channels));
+
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            let text_style = egui::TextStyle::Body;
+            let row_height = ui.fonts()[text_style].row_height();
+            let mut rows = vec![];
+            for (index, message) in self.messages.iter().enumerate() {
+                if !self.filter.matches(message) {
+                    continue;
+                }
+
+                rows.push(DisplayRow::Message(index));
+                match &message.content {
+                    Content::Table(table) => rows.extend(
+                        (0..table.rows().len()).map(|row| DisplayRow::TableRow(index, row)),
+                    ),
+                    Content::Block { lines, .. } if !message.collapsed => {
+                        rows.extend((0..lines.len()).map(|line| DisplayRow::BlockLine(index, line)))
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut clicked = None;
+            let mut toggled = None;
+            egui::ScrollArea::vertical().show_rows(ui, row_height, rows.len(), |ui, row_range| {
+                for row in &rows[row_range] {
+                    ui.horizontal_wrapped(|ui| {
+                        self.row_ui(ui, *row, &mut clicked, &mut toggled);
+                    });
+                }
+            });
+
+            if let Some(index) = toggled {
+                self.messages[index].collapsed ^= true;
+            }
+            if let Some(link) = clicked {
+                self.follow_link(link);
+            }
+        });
+    }
+
+    fn row_ui(
+        &self,
+        ui: &mut Ui,
+        row: DisplayRow,
+        clicked: &mut Option<Link>,
+        toggled: &mut Option<usize>,
+    ) {
+        let (DisplayRow::Message(index)
+        | DisplayRow::TableRow(index, _)
+        | DisplayRow::BlockLine(index, _)) = row;
+        let message = &self.messages[index];
+        let color = {
+            let (r, g, b) = message.message_type.color();
+            egui::Color32::from_rgb(r, g, b)
+        };
+
+        let time = message.time.format("%T").to_string();
+        let mut response = match row {
+            DisplayRow::Message(_) => {
+                let (r, g, b) = TIME_COLOR;
+                ui.colored_label(egui::Color32::from_rgb(r, g, b), time)
+            }
+            // Continuation rows are indented to line up with the first row.
+            _ => ui.label(RichText::new(time).color(egui::Color32::TRANSPARENT)),
+        };
+
+        ui.spacing_mut().item_spacing.x = 0.0;
+        let content = match (row, &message.content) {
+            (DisplayRow::Message(_), Content::Text | Content::Markup { .. }) => {
+                let job = match &message.content {
+                    Content::Markup { segments, .. } => {
+                        let segments: Vec<_> = segments
+                            .iter()
+                            .map(|segment| {
+                                let color = segment
+                                    .color
+                                    .map_or(color, |(r, g, b)| egui::Color32::from_rgb(r, g, b));
+                                (segment.text.as_str(), color)
+                            })
+                            .collect();
+                        self.filter.layout_segments(&segments)
+                    }
+                    _ => self.filter.layout(&message.text, color),
+                };
+
+                let mut response = ui.label(job);
+                if message.repeats > 1 {
+                    let badge = ui
+                        .label(RichText::new(format!("  ×{}", message.repeats)).weak())
+                        .on_hover_text(format!(
+                            "Last seen at {}",
+                            message.last_time.format("%T%.3f")
+                        ));
+                    response = response.union(badge);
+                }
+                Some(response)
+            }
+            (DisplayRow::Message(_), Content::Spans(spans)) => spans
+                .iter()
+                .map(|span| match &span.link {
+                    Some(link) => {
+                        output::span_ui(ui, RichText::new(&span.text), Some(link), clicked)
+                    }
+                    None => ui.label(self.filter.layout(&span.text, color)),
+                })
+                .reduce(|a, b| a.union(b)),
+            (DisplayRow::Message(_), Content::Table(table)) => {
+                table.row_ui(ui, None, color, clicked)
+            }
+            (DisplayRow::TableRow(_, row), Content::Table(table)) => {
+                table.row_ui(ui, Some(row), color, clicked)
+            }
+            (DisplayRow::Message(_), Content::Block { title, lines }) => {
+                let arrow = if message.collapsed { "⏵" } else { "⏷" };
+                let text = format!("{} {} ({} lines)", arrow, title, lines.len());
+                let response = ui.add(
+                    egui::Label::new(RichText::new(text).color(color)).sense(egui::Sense::click()),
+                );
+                if response.clicked() {
+                    *toggled = Some(index);
+                }
+                Some(response)
+            }
+            (DisplayRow::BlockLine(_, line), Content::Block { lines, .. }) => {
+                Some(ui.label(self.filter.layout(&format!("    {}", lines[line]), color)))
+            }
+            _ => None,
+        };
+        if let Some(content) = content {
+            response = response.union(content);
+        }
+
+        response.context_menu(|ui| {
+            if ui.button("Copy").clicked() {
+                ui.output().copied_text = message.text.clone();
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn completion_ui(&mut self, ui: &mut Ui, input_id: egui::Id) {
+        let mut clicked = None;
+        if let Some(completion) = &self.completion {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (index, candidate) in completion.candidates().iter().enumerate() {
+                        if ui
+                            .selectable_label(index == completion.index(), candidate)
+                            .clicked()
+                        {
+                            clicked = Some(index);
+                        }
+                    }
+                });
+            });
+        }
+
+        if let (Some(index), Some(completion)) = (clicked, &mut self.completion) {
+            completion.select(index);
+            self.text_input = completion.line();
+            ui.memory().request_focus(input_id);
+            Self::move_cursor_to_end(ui, input_id, &self.text_input);
+        }
+    }
+
+    /// Completes the word at the end of the input line, cycling through the candidates
+    /// on repeated calls. Returns the completed line, if any.
+    fn complete(&mut self) -> Option<String> {
+        if let Some(completion) = &mut self.completion {
+            completion.cycle();
+            return Some(completion.line());
+        }
+
+        let input = completion::split_input(&self.text_input);
+        let args: Vec<_> = input.args.iter().map(String::as_str).collect();
+        let candidates = match args.split_first() {
+            None => self.commands.keys().cloned().collect(),
+            Some((keyword, args)) => match self.commands.get(*keyword) {
+                Some(RegisteredCommand {
+                    completer: Some(completer),
+                    ..
+                }) => completer(self, args),
+                Some(RegisteredCommand {
+                    signature: Some(signature),
+                    ..
+                }) => signature.candidates(args),
+                _ => vec![],
+            },
+        };
+
+        let prefix = self.text_input[..input.start].to_owned();
+        let candidates = completion::filter_candidates(candidates, &input.partial);
+        let completion = Completion::new(prefix, candidates)?;
+        if completion.is_unique() {
+            Some(completion.line() + " ")
+        } else {
+            let line = completion.line();
+            self.completion = Some(completion);
+            Some(line)
+        }
+    }
+
+    fn move_cursor_to_end(ui: &Ui, id: egui::Id, text: &str) {
+        if let Some(mut state) = TextEditState::load(ui.ctx(), id) {
+            let ccursor = CCursor::new(text.chars().count());
+            state.set_ccursor_range(Some(CCursorRange::one(ccursor)));
+            state.store(ui.ctx(), id);
+        }
+    }
+
+    fn enter_command(&mut self) {
+        let input = std::mem::take(&mut self.text_input);
+        self.submit(&input);
+    }
+
+    /// Echoes `line` and adds it to the history before executing it, as if it had been
+    /// typed into the console.
+    pub fn submit(&mut self, line: &str) {
+        self.push_back_message(format!("> {}", line), MessageType::Command);
+
+        self.history.push(line);
+        if let Err(err) = self.history.save() {
+            self.push_back_error(format!("Failed to save console history: {}", err));
+        }
+
+        self.execute(line);
+    }
+
+    /// Executes every command on `line`, reporting any errors to the console.
+    pub fn execute(&mut self, line: &str) {
+        match tokenizer::tokenize(line) {
+            Ok(commands) if commands.is_empty() => {
+                self.push_back_error("Invalid command.".into());
+            }
+            Ok(commands) => {
+                self.queue_commands(None, commands);
+                self.run_queued_commands();
+            }
+            Err(err) => self.push_back_error(err.to_string()),
+        }
+    }
+
+    /// Executes the console script at `path`, which is relative to the directory of
+    /// `payload.dll` unless absolute.
+    pub fn exec_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        script::queue_file(self, path)?;
+        self.run_queued_commands();
+        Ok(())
+    }
+
+    /// Executes `autoexec.cfg` from the directory of `payload.dll`, if it exists.
+    pub fn exec_autoexec(&mut self) {
+        let path = Path::new(script::AUTOEXEC_FILENAME);
+        let exists = crate::paths::payload_file(script::AUTOEXEC_FILENAME)
+            .map(|p| p.exists())
+            .unwrap_or_default();
+
+        if exists {
+            self.push_back_info(format!("Executing {}", script::AUTOEXEC_FILENAME));
+            if let Err(err) = self.exec_file(path) {
+                self.push_back_error(err.to_string());
+            }
+        }
+    }
+
+    /// Queues commands to run before any that are already queued. Errors from the commands
+    /// are prefixed with `source`, if given.
+    fn queue_commands(&mut self, source: Option<String>, commands: Vec<Vec<String>>) {
+        for words in commands.into_iter().rev() {
+            self.queue.push_front(QueuedCommand {
+                source: source.clone(),
+                words,
+            });
+        }
+    }
+
+    /// Runs queued commands until none are left. Commands can queue further commands
+    /// (e.g. `exec`), which run before the rest of the queue.
+    fn run_queued_commands(&mut self) {
+        if self.running_queue {
+            return;
+        }
+        self.running_queue = true;
+
+        let mut count = 0;
+        while let Some(QueuedCommand { source, words }) = self.queue.pop_front() {
+            count += 1;
+            if count > MAX_QUEUED_COMMANDS {
+                self.queue.clear();
+                self.push_back_error(format!(
+                    "Stopped after running {} commands; is a script executing itself?",
+                    MAX_QUEUED_COMMANDS
+                ));
+                break;
+            }
+
+            let words: Vec<_> = words.iter().map(|s| s.as_str()).collect();
+            if let Err(err) = self.run_command(words[0], &words[1..]) {
+                match source {
+                    Some(source) => self.push_back_error(format!("{}: {}", source, err)),
+                    None => self.push_back_error(err.to_string()),
+                }
+            }
+        }
+
+        self.running_queue = false;
+    }
+
+    fn run_command(&mut self, keyword: &str, arguments: &[&str]) -> anyhow::Result<()> {
+        let command = self
+            .commands
+            .get_mut(keyword)
+            .ok_or_else(|| anyhow::anyhow!("The command `{}` does not exist.", keyword))?;
+
+        let handle = command.handle;
+        let args = match &command.signature {
+            Some(signature) => Some(
+                signature
+                    .parse(arguments, self.environment.as_ref())
+                    .map_err(|err| anyhow::anyhow!("{}. Usage: {}", err, signature.usage()))?,
+            ),
+            None => None,
+        };
+
+        let mut callback = command
+            .callback
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("The command `{}` is already running.", keyword))?;
+
+        let result = match (&mut callback, &args) {
+            (Callback::Raw(callback), _) => callback(self, keyword, arguments),
+            (Callback::Typed(callback), Some(args)) => callback(self, args),
+            (Callback::Typed(_), None) => unreachable!("typed commands always have a signature"),
+        };
+
+        // The command may have removed or replaced itself while it was running.
+        if let Some(command) = self.commands.get_mut(keyword) {
+            if command.handle == handle {
+                command.callback = Some(callback);
+            }
+        }
+
+        result
+    }
+
+    fn help(&mut self, keyword: &str) -> anyhow::Result<()> {
+        if keyword.is_empty() {
+            let mut lines: Vec<_> = self
+                .commands
+                .iter()
+                .map(|(name, command)| match &command.signature {
+                    Some(signature) => format!("{} - {}", name, signature.description()),
+                    None => name.clone(),
+                })
+                .collect();
+            lines.sort();
+
+            for line in lines {
+                self.push_back_info(line);
+            }
+            return Ok(());
+        }
+
+        let signature = match self.commands.get(keyword) {
+            Some(command) => command.signature.clone(),
+            None => anyhow::bail!("The command `{}` does not exist.", keyword),
+        };
+
+        match signature {
+            Some(signature) => {
+                self.push_back_info(format!("Usage: {}", signature.usage()));
+                self.push_back_info(signature.description().to_owned());
+                for line in signature.argument_help() {
+                    self.push_back_info(line);
+                }
+            }
+            None => self.push_back_info(format!("`{}` has no description.", keyword)),
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref CHANNEL: (ConsoleSender, Mutex<Option<Receiver<Event>>>) = {
+        let (sender, receiver) = mpsc::channel();
+        (ConsoleSender(sender), Mutex::new(Some(receiver)))
+    };
+    /// Logs to and runs commands on [`CONSOLE`] from any thread, including from within
+    /// command callbacks and detours, without waiting for its lock.
+    pub static ref CONSOLE_SENDER: ConsoleSender = CHANNEL.0.clone();
+    pub static ref CONSOLE: Mutex<Console> = {
+        let receiver = CHANNEL.1.lock().unwrap().take().unwrap();
+        let directory = crate::paths::payload_directory().ok();
+        Mutex::new(Console::with_channel(CHANNEL.0.clone(), receiver, directory))
+    };
+}
+
+/// Evaluates an address typed into a panel with [`CONSOLE`]'s symbols and variables.
+///
+/// This locks [`CONSOLE`], so it must not be called while the console is locked, such as from
+/// a command. Panels are drawn while it is unlocked, which makes this safe to call from them.
+pub fn evaluate_address(text: &str) -> anyhow::Result<usize> {
+    let address = CONSOLE.lock().unwrap().evaluate(text)?;
+    Ok(address as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// A console that keeps its files in a directory of its own for `test`.
+    fn console(test: &str) -> (Console, PathBuf) {
+        let directory =
+            std::env::temp_dir().join(format!("console-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        (Console::in_directory(Some(directory.clone())), directory)
+    }
+
+    fn messages(console: &Console) -> Vec<(&str, u32)> {
+        console
+            .messages
+            .iter()
+            .map(|message| (message.text(), message.repeats()))
+            .collect()
+    }
+
+    #[test]
+    fn coalesces_repeated_messages() {
+        let (mut console, directory) = console("coalesce");
+        console.cvars_mut().set("log_file", false).unwrap();
+        for _ in 0..3 {
+            console.push_back_info("again".into());
+        }
+        console.push_back_error("again".into());
+        console.push_back_channel_message("other", "again".into(), MessageType::Error);
+        console.push_back_info("{red}red{reset}".into());
+        console.push_back_info("{red}red{reset}".into());
+
+        assert_eq!(
+            messages(&console),
+            [("again", 3), ("again", 1), ("again", 1), ("red", 2)]
+        );
+        assert_eq!(console.message_count(), 4);
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn logs_repeats_once_a_different_message_arrives() {
+        let (mut console, directory) = console("log_repeats");
+        let log = directory.join("logs").join("console.log");
+        for _ in 0..3 {
+            console.push_back_info("again".into());
+        }
+        let contents = fs::read_to_string(&log).unwrap();
+        assert_eq!(contents.matches("again").count(), 1);
+        assert!(!contents.contains("repeated"));
+
+        console.push_back_info("different".into());
+        let contents = fs::read_to_string(&log).unwrap();
+        let repeated = contents.find("(last message repeated 3 times)").unwrap();
+        assert!(repeated < contents.find("different").unwrap());
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
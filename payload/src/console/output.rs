@@ -0,0 +1,216 @@
+use egui::{Color32, Response, RichText, Sense, Ui};
+
+use super::markup::Segment;
+
+/// Runs the link handler named `handler` with `argument` when the span it is on is clicked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    pub handler: String,
+    pub argument: String,
+}
+
+/// A piece of a message, which may be clickable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub link: Option<Link>,
+}
+
+impl Span {
+    pub fn link(text: impl Into<String>, handler: &str, argument: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            link: Some(Link {
+                handler: handler.to_owned(),
+                argument: argument.into(),
+            }),
+        }
+    }
+
+    /// A span that runs `line` in the console when clicked.
+    pub fn command(text: impl Into<String>, line: impl Into<String>) -> Self {
+        Self::link(text, COMMAND_HANDLER, line)
+    }
+}
+
+impl From<&str> for Span {
+    fn from(text: &str) -> Self {
+        text.to_owned().into()
+    }
+}
+
+impl From<String> for Span {
+    fn from(text: String) -> Self {
+        Self { text, link: None }
+    }
+}
+
+/// The link handler that runs its argument as a command line.
+pub const COMMAND_HANDLER: &str = "command";
+
+/// Rows of cells shown with their columns aligned.
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<Span>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: vec![],
+        }
+    }
+
+    pub fn push_row<S: Into<Span>>(&mut self, cells: impl IntoIterator<Item = S>) {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+
+    pub fn rows(&self) -> &[Vec<Span>] {
+        &self.rows
+    }
+
+    /// The width of each column in characters.
+    fn widths(&self) -> Vec<usize> {
+        let columns = self
+            .rows
+            .iter()
+            .map(Vec::len)
+            .chain(std::iter::once(self.headers.len()))
+            .max()
+            .unwrap_or(0);
+
+        (0..columns)
+            .map(|column| {
+                let header = self.headers.get(column).map(String::as_str);
+                let cells = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(column).map(|cell| cell.text.as_str()));
+                header
+                    .into_iter()
+                    .chain(cells)
+                    .map(|text| text.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    fn line<'a>(cells: impl Iterator<Item = &'a str>, widths: &[usize]) -> String {
+        let cells: Vec<_> = cells
+            .zip(widths)
+            .map(|(text, width)| format!("{:width$}", text, width = width))
+            .collect();
+        cells.join("  ").trim_end().to_owned()
+    }
+
+    /// Returns the table as aligned lines of text, starting with the headers.
+    pub fn lines(&self) -> Vec<String> {
+        let widths = self.widths();
+        std::iter::once(Self::line(self.headers.iter().map(String::as_str), &widths))
+            .chain(
+                self.rows
+                    .iter()
+                    .map(|row| Self::line(row.iter().map(|cell| cell.text.as_str()), &widths)),
+            )
+            .collect()
+    }
+
+    /// Draws the headers (`row` is `None`) or one of the rows, padded to line up.
+    pub fn row_ui(
+        &self,
+        ui: &mut Ui,
+        row: Option<usize>,
+        color: Color32,
+        clicked: &mut Option<Link>,
+    ) -> Option<Response> {
+        let widths = self.widths();
+        let cells: Vec<Span> = match row {
+            None => self
+                .headers
+                .iter()
+                .map(|h| Span::from(h.as_str()))
+                .collect(),
+            Some(row) => self.rows.get(row)?.clone(),
+        };
+
+        let mut response: Option<Response> = None;
+        for (cell, width) in cells.iter().zip(&widths) {
+            let mut text = RichText::new(format!("{:width$}", cell.text, width = width + 2))
+                .monospace()
+                .color(color);
+            if row.is_none() {
+                text = text.strong();
+            }
+
+            let cell_response = span_ui(ui, text, cell.link.as_ref(), clicked);
+            response = Some(match response {
+                Some(response) => response.union(cell_response),
+                None => cell_response,
+            });
+        }
+        response
+    }
+}
+
+/// What a message shows, beyond its plain text.
+#[derive(Clone, Debug)]
+pub enum Content {
+    Text,
+    /// Text with inline colours, keeping the `source` it was parsed from.
+    Markup {
+        source: String,
+        segments: Vec<Segment>,
+    },
+    Spans(Vec<Span>),
+    Table(Table),
+    /// A title that can be expanded to show the lines beneath it.
+    Block {
+        title: String,
+        lines: Vec<String>,
+    },
+}
+
+impl Content {
+    /// Returns the content as plain text, for logs and for copying.
+    pub fn plain_text(&self) -> Option<String> {
+        match self {
+            Content::Text => None,
+            Content::Markup { segments, .. } => {
+                Some(segments.iter().map(|s| s.text.as_str()).collect())
+            }
+            Content::Spans(spans) => Some(spans.iter().map(|s| s.text.as_str()).collect()),
+            Content::Table(table) => Some(table.lines().join("\n")),
+            Content::Block { title, lines } => Some(
+                std::iter::once(title.as_str())
+                    .chain(lines.iter().map(|line| line.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }
+    }
+}
+
+/// Draws `text`, as a link if it has one, recording the link if it was clicked.
+pub fn span_ui(
+    ui: &mut Ui,
+    text: RichText,
+    link: Option<&Link>,
+    clicked: &mut Option<Link>,
+) -> Response {
+    match link {
+        Some(link) => {
+            let text = text.underline().color(ui.visuals().hyperlink_color);
+            let response = ui
+                .add(egui::Label::new(text).sense(Sense::click()))
+                .on_hover_text(format!("{} {}", link.handler, link.argument));
+            if response.clicked() {
+                *clicked = Some(link.clone());
+            }
+            response
+        }
+        None => ui.label(text),
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use super::{Cvar, CvarRegistry};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+struct Window {
+    start: Instant,
+    count: u32,
+    suppressed: u32,
+}
+
+/// Limits how many messages each channel can show a second, counting the rest so that
+/// a notice can be shown once the flood is over.
+pub struct RateLimiter {
+    windows: HashMap<String, Window>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self {
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Returns whether a message to `channel` should be shown, given that at most `limit`
+    /// are shown a second. A `limit` of 0 lets every message through.
+    pub fn allow(&mut self, channel: &str, limit: u32, now: Instant) -> bool {
+        if limit == 0 {
+            return true;
+        }
+
+        let window = self
+            .windows
+            .entry(channel.to_owned())
+            .or_insert_with(|| Window {
+                start: now,
+                count: 0,
+                suppressed: 0,
+            });
+        // Messages suppressed in the previous window are still reported by `take_suppressed`.
+        if now.duration_since(window.start) >= WINDOW {
+            window.start = now;
+            window.count = 0;
+        }
+
+        if window.count < limit {
+            window.count += 1;
+            true
+        } else {
+            window.suppressed += 1;
+            false
+        }
+    }
+
+    /// Returns how many messages were suppressed on each channel whose window has ended,
+    /// forgetting those channels until they are sent to again.
+    pub fn take_suppressed(&mut self, now: Instant) -> Vec<(String, u32)> {
+        let mut suppressed = vec![];
+        self.windows.retain(|channel, window| {
+            if now.duration_since(window.start) < WINDOW {
+                return true;
+            }
+            if window.suppressed > 0 {
+                suppressed.push((channel.clone(), window.suppressed));
+            }
+            false
+        });
+        suppressed.sort();
+        suppressed
+    }
+}
+
+pub fn register_cvars(cvars: &mut CvarRegistry) -> anyhow::Result<()> {
+    cvars.register(
+        Cvar::new(
+            "console_rate_limit",
+            "The most messages a second shown from each channel when they are sent from other threads, or 0 for no limit.",
+            200_i64,
+        )
+        .range(0.0, 1_000_000.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_up_to_the_limit_a_second() {
+        let mut limiter = RateLimiter::new();
+        let start = Instant::now();
+        let allowed = (0..5).filter(|_| limiter.allow("hooks", 3, start)).count();
+        assert_eq!(allowed, 3);
+        assert!(limiter.allow("other", 3, start));
+    }
+
+    #[test]
+    fn allows_more_once_the_window_ends() {
+        let mut limiter = RateLimiter::new();
+        let start = Instant::now();
+        assert!(limiter.allow("hooks", 1, start));
+        assert!(!limiter.allow("hooks", 1, start + Duration::from_millis(999)));
+        assert!(limiter.allow("hooks", 1, start + WINDOW));
+        assert!(!limiter.allow("hooks", 1, start + WINDOW));
+    }
+
+    #[test]
+    fn no_limit_allows_everything() {
+        let mut limiter = RateLimiter::new();
+        let now = Instant::now();
+        assert!((0..1000).all(|_| limiter.allow("hooks", 0, now)));
+        assert!(limiter.take_suppressed(now + WINDOW).is_empty());
+    }
+
+    #[test]
+    fn reports_suppressed_counts_once_the_window_ends() {
+        let mut limiter = RateLimiter::new();
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.allow("b", 2, start);
+            limiter.allow("a", 4, start);
+        }
+        limiter.allow("quiet", 2, start);
+
+        assert!(limiter.take_suppressed(start).is_empty());
+        assert_eq!(
+            limiter.take_suppressed(start + WINDOW),
+            [("a".to_owned(), 1), ("b".to_owned(), 3)]
+        );
+        assert!(limiter.take_suppressed(start + WINDOW * 2).is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+
+use super::{tokenizer, ArgType, CommandSignature, Console};
+
+pub const AUTOEXEC_FILENAME: &str = "autoexec.cfg";
+
+/// Queues every command in the script at `path` to run before any already queued commands.
+///
+/// Scripts have one command line per line; blank lines and lines starting with `//` or `#`
+/// are ignored. Lines that cannot be tokenized are reported and skipped.
+pub fn queue_file(console: &mut Console, path: &Path) -> anyhow::Result<()> {
+    let path = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => crate::paths::payload_directory()?.join(path),
+    };
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read `{}`", path.display()))?;
+    let filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut lines = vec![];
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+            continue;
+        }
+
+        let source = format!("{}:{}", filename, index + 1);
+        match tokenizer::tokenize(line) {
+            Ok(commands) => lines.push((source, commands)),
+            Err(err) => console.push_back_error(format!("{}: {}", source, err)),
+        }
+    }
+
+    for (source, commands) in lines.into_iter().rev() {
+        console.queue_commands(Some(source), commands);
+    }
+    Ok(())
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command(
+        CommandSignature::new("exec", "Runs the commands in a script file.").arg(
+            "file",
+            ArgType::String,
+            "The script to run, relative to payload.dll.",
+        ),
+        |console, args| queue_file(console, Path::new(args.string("file")?)),
+    );
+}
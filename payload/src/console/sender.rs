@@ -0,0 +1,161 @@
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+
+use super::{Console, MessageType, DEFAULT_CHANNEL};
+
+/// How long [`ConsoleSender::run_and_wait`] leaves its work for the overlay before doing it
+/// itself, which is long enough for a frame even at low frame rates.
+const UNPROCESSED_DELAY: Duration = Duration::from_millis(250);
+
+/// Work sent to the console from other threads, applied by [`Console::process_events`].
+pub enum Event {
+    Message {
+        time: DateTime<Local>,
+        channel: String,
+        text: String,
+        message_type: MessageType,
+    },
+    Execute(String),
+    Run(Box<dyn FnOnce(&mut Console) + Send>),
+}
+
+/// Logs to and runs commands on a console from any thread without locking it. Everything
+/// sent is applied in order the next time the console processes its events, which the
+/// overlay does once per frame.
+#[derive(Clone)]
+pub struct ConsoleSender(pub(super) Sender<Event>);
+
+impl ConsoleSender {
+    fn send(&self, event: Event) {
+        // The console only goes away when the payload is unloading, so there is nobody
+        // left to tell.
+        let _ = self.0.send(event);
+    }
+
+    pub fn push_back_message(&self, message: String, message_type: MessageType) {
+        self.push_back_channel_message(DEFAULT_CHANNEL, message, message_type);
+    }
+
+    pub fn push_back_channel_message(
+        &self,
+        channel: &str,
+        message: String,
+        message_type: MessageType,
+    ) {
+        self.send(Event::Message {
+            time: Local::now(),
+            channel: channel.to_owned(),
+            text: message,
+            message_type,
+        });
+    }
+
+    pub fn push_back_info(&self, message: String) {
+        self.push_back_message(message, MessageType::Info);
+    }
+
+    pub fn push_back_error(&self, message: String) {
+        self.push_back_message(message, MessageType::Error);
+    }
+
+    /// Runs a command line as if it had been entered.
+    pub fn execute(&self, line: &str) {
+        self.send(Event::Execute(line.to_owned()));
+    }
+
+    /// Runs `callback` with the console.
+    pub fn run(&self, callback: impl FnOnce(&mut Console) + Send + 'static) {
+        self.send(Event::Run(Box::new(callback)));
+    }
+
+    /// Runs `callback` with `console`, the console this sends to, and returns its result.
+    /// It is run in order with everything else sent, normally when the overlay next
+    /// processes the console's events; if no frame does so soon, such as while the game is
+    /// minimised or the overlay is not drawn at all, this thread processes them instead.
+    ///
+    /// This must not be called with `console` locked.
+    pub fn run_and_wait<T: Send + 'static>(
+        &self,
+        console: &Mutex<Console>,
+        callback: impl FnOnce(&mut Console) -> T + Send + 'static,
+    ) -> anyhow::Result<T> {
+        let (result_tx, result_rx) = mpsc::channel();
+        self.run(move |console| {
+            let _ = result_tx.send(callback(console));
+        });
+
+        loop {
+            match result_rx.recv_timeout(UNPROCESSED_DELAY) {
+                Ok(result) => return Ok(result),
+                Err(RecvTimeoutError::Timeout) => console
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("The console is poisoned."))?
+                    .process_events(),
+                Err(RecvTimeoutError::Disconnected) => {
+                    anyhow::bail!("The console was dropped before it ran the callback.")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    };
+
+    use super::*;
+
+    fn console() -> &'static Mutex<Console> {
+        let mut console = Console::in_directory(None);
+        console.cvars_mut().set("log_file", false).unwrap();
+        Box::leak(Box::new(Mutex::new(console)))
+    }
+
+    #[test]
+    fn runs_without_a_frame() {
+        let console = console();
+        let sender = console.lock().unwrap().sender();
+        let output = sender
+            .run_and_wait(console, |console| {
+                let count = console.message_count();
+                console.execute("echo hello");
+                let texts: Vec<_> = console
+                    .messages_since(count)
+                    .map(|message| message.text().to_owned())
+                    .collect();
+                texts
+            })
+            .unwrap();
+        assert_eq!(output, ["hello"]);
+    }
+
+    #[test]
+    fn runs_on_the_thread_processing_events() {
+        let console = console();
+        let sender = console.lock().unwrap().sender();
+        let stopping = Box::leak(Box::new(AtomicBool::new(false)));
+        let overlay = thread::spawn(|| {
+            while !stopping.load(Ordering::SeqCst) {
+                console.lock().unwrap().process_events();
+                thread::sleep(Duration::from_millis(5));
+            }
+            thread::current().id()
+        });
+
+        let ran_on = sender
+            .run_and_wait(console, |_| thread::current().id())
+            .unwrap();
+        stopping.store(true, Ordering::SeqCst);
+        assert_eq!(ran_on, overlay.join().unwrap());
+    }
+}
@@ -0,0 +1,290 @@
+//! Splits console input into commands and their arguments.
+//!
+//! The grammar is a small subset of a POSIX shell:
+//! - arguments are separated by whitespace, and commands by `;`
+//! - `"double quotes"` group text into one argument; `\"` and `\\` are escapes within them
+//! - `'single quotes'` group text literally, with no escapes
+//! - outside of quotes, a backslash escapes whitespace, quotes, `;` and itself
+//!
+//! Backslashes before any other character are kept as-is, so that Windows paths like
+//! `C:\Windows\System32` do not need to be escaped.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenizeError {
+    UnterminatedQuote { quote: char, position: usize },
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenizeError::UnterminatedQuote { quote, position } => write!(
+                f,
+                "Unterminated {} quote starting at column {}.",
+                match quote {
+                    '"' => "double",
+                    _ => "single",
+                },
+                position + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TokenizeError {}
+
+/// Tokenizes `input` into a list of commands, each of which is a non-empty list of words.
+pub fn tokenize(input: &str) -> Result<Vec<Vec<String>>, TokenizeError> {
+    let mut scan = scan(input);
+    if let Some(error) = scan.error {
+        return Err(error);
+    }
+    scan.words.extend(scan.word.map(|(_, word)| word));
+    if !scan.words.is_empty() {
+        scan.commands.push(scan.words);
+    }
+    Ok(scan.commands)
+}
+
+/// Tokenizes the last command in `input`, which is still being typed, returning the words
+/// before the last one and the last one with where it starts, or `None` if the input ends
+/// between words. A word in an unterminated quote is the last word.
+pub fn tokenize_partial(input: &str) -> (Vec<String>, Option<(usize, String)>) {
+    let scan = scan(input);
+    (scan.words, scan.word)
+}
+
+/// What was read from an input, up to its end or an unterminated quote.
+struct Scan {
+    /// The commands ended by `;`.
+    commands: Vec<Vec<String>>,
+    /// The words of the command that has not been ended.
+    words: Vec<String>,
+    /// The word that has not been ended, and where it starts.
+    word: Option<(usize, String)>,
+    error: Option<TokenizeError>,
+}
+
+fn scan(input: &str) -> Scan {
+    let mut commands = vec![];
+    let mut words = vec![];
+    // `None` until the current word has any content, so that `""` can produce an empty word.
+    let mut word: Option<(usize, String)> = None;
+
+    let mut chars = input.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take().map(|(_, word)| word)),
+            ';' => {
+                words.extend(word.take().map(|(_, word)| word));
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            '\\' => {
+                let (_, text) = word.get_or_insert_with(|| (position, String::new()));
+                match chars.peek() {
+                    Some(&(_, next)) if is_escapable(next) => {
+                        text.push(next);
+                        chars.next();
+                    }
+                    _ => text.push('\\'),
+                }
+            }
+            '"' | '\'' => {
+                let quote = c;
+                let (_, text) = word.get_or_insert_with(|| (position, String::new()));
+                loop {
+                    match chars.next() {
+                        Some((_, c)) if c == quote => break,
+                        Some((_, '\\')) if quote == '"' => match chars.peek() {
+                            Some(&(_, next)) if next == '"' || next == '\\' => {
+                                text.push(next);
+                                chars.next();
+                            }
+                            _ => text.push('\\'),
+                        },
+                        Some((_, c)) => text.push(c),
+                        None => {
+                            return Scan {
+                                commands,
+                                words,
+                                word,
+                                error: Some(TokenizeError::UnterminatedQuote { quote, position }),
+                            }
+                        }
+                    }
+                }
+            }
+            c => word
+                .get_or_insert_with(|| (position, String::new()))
+                .1
+                .push(c),
+        }
+    }
+
+    Scan {
+        commands,
+        words,
+        word,
+        error: None,
+    }
+}
+
+fn is_escapable(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '"' | '\'' | ';' | '\\')
+}
+
+/// Quotes `text` so that it is read back as a single word by the tokenizer.
+pub fn quote(text: &str) -> String {
+    if !text.is_empty() && !text.chars().any(is_escapable) {
+        return text.to_owned();
+    }
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenize_ok(input: &str) -> Vec<Vec<String>> {
+        tokenize(input).unwrap()
+    }
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            tokenize_ok("  echo hello\tworld  "),
+            vec![words(&["echo", "hello", "world"])]
+        );
+    }
+
+    #[test]
+    fn empty_input_has_no_commands() {
+        assert!(tokenize_ok("").is_empty());
+        assert!(tokenize_ok("   ").is_empty());
+        assert!(tokenize_ok(" ; ;; ").is_empty());
+    }
+
+    #[test]
+    fn double_quotes_preserve_spacing() {
+        assert_eq!(
+            tokenize_ok(r#"echo "hello   world""#),
+            vec![words(&["echo", "hello   world"])]
+        );
+    }
+
+    #[test]
+    fn single_quotes_are_literal() {
+        assert_eq!(
+            tokenize_ok(r#"echo 'a \" b' 'it;s'"#),
+            vec![words(&["echo", r#"a \" b"#, "it;s"])]
+        );
+    }
+
+    #[test]
+    fn empty_quotes_produce_empty_word() {
+        assert_eq!(tokenize_ok(r#"echo "" ''"#), vec![words(&["echo", "", ""])]);
+    }
+
+    #[test]
+    fn adjacent_quoted_and_unquoted_text_join() {
+        assert_eq!(
+            tokenize_ok(r#"echo foo"bar baz"'qux'"#),
+            vec![words(&["echo", "foobar bazqux"])]
+        );
+    }
+
+    #[test]
+    fn escapes_in_double_quotes() {
+        assert_eq!(
+            tokenize_ok(r#"echo "say \"hi\" \\ \n""#),
+            vec![words(&["echo", r#"say "hi" \ \n"#])]
+        );
+    }
+
+    #[test]
+    fn escapes_outside_quotes() {
+        assert_eq!(
+            tokenize_ok(r#"echo a\ b \"c\" d\;e f\\g"#),
+            vec![words(&["echo", "a b", r#""c""#, "d;e", r"f\g"])]
+        );
+    }
+
+    #[test]
+    fn windows_paths_keep_backslashes() {
+        assert_eq!(
+            tokenize_ok(r#"exec C:\Windows\System32 "C:\Program Files\HITMAN 3" D:\"#),
+            vec![words(&[
+                "exec",
+                r"C:\Windows\System32",
+                r"C:\Program Files\HITMAN 3",
+                r"D:\"
+            ])]
+        );
+    }
+
+    #[test]
+    fn semicolons_separate_commands() {
+        assert_eq!(
+            tokenize_ok("echo a; echo b;echo c ;"),
+            vec![
+                words(&["echo", "a"]),
+                words(&["echo", "b"]),
+                words(&["echo", "c"])
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_semicolons_do_not_separate_commands() {
+        assert_eq!(
+            tokenize_ok(r#"echo "a; b""#),
+            vec![words(&["echo", "a; b"])]
+        );
+    }
+
+    #[test]
+    fn unterminated_quotes_are_errors() {
+        assert_eq!(
+            tokenize(r#"echo "hello"#),
+            Err(TokenizeError::UnterminatedQuote {
+                quote: '"',
+                position: 5
+            })
+        );
+        assert_eq!(
+            tokenize("echo a; echo 'b"),
+            Err(TokenizeError::UnterminatedQuote {
+                quote: '\'',
+                position: 13
+            })
+        );
+        assert_eq!(
+            tokenize(r#"echo "\""#),
+            Err(TokenizeError::UnterminatedQuote {
+                quote: '"',
+                position: 5
+            })
+        );
+    }
+
+    #[test]
+    fn quoted_text_tokenizes_to_itself() {
+        for text in [
+            "plain",
+            "",
+            "two words",
+            r#"say "hi""#,
+            r"C:\Program Files\",
+            "a;b",
+        ] {
+            assert_eq!(tokenize_ok(&quote(text)), vec![words(&[text])]);
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod reflection;
+pub mod types;
 pub mod zapplication_engine_win32;
 pub mod zrender;
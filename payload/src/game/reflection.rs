@@ -0,0 +1,139 @@
+//! Descriptions of the game types generated from the schema, for tools that show them at
+//! runtime.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Primitive {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    Bool,
+}
+
+impl Primitive {
+    pub fn name(self) -> &'static str {
+        match self {
+            Primitive::U8 => "u8",
+            Primitive::I8 => "i8",
+            Primitive::U16 => "u16",
+            Primitive::I16 => "i16",
+            Primitive::U32 => "u32",
+            Primitive::I32 => "i32",
+            Primitive::U64 => "u64",
+            Primitive::I64 => "i64",
+            Primitive::F32 => "f32",
+            Primitive::F64 => "f64",
+            Primitive::Bool => "bool",
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Primitive::U8 | Primitive::I8 | Primitive::Bool => 1,
+            Primitive::U16 | Primitive::I16 => 2,
+            Primitive::U32 | Primitive::I32 | Primitive::F32 => 4,
+            Primitive::U64 | Primitive::I64 | Primitive::F64 => 8,
+        }
+    }
+
+    /// Formats the value in `bytes`, which must be [`Primitive::size`] bytes long.
+    pub fn format(self, bytes: &[u8]) -> String {
+        macro_rules! format_as {
+            ($ty:ty) => {
+                <$ty>::from_le_bytes(bytes.try_into().unwrap()).to_string()
+            };
+        }
+        match self {
+            Primitive::U8 => format_as!(u8),
+            Primitive::I8 => format_as!(i8),
+            Primitive::U16 => format_as!(u16),
+            Primitive::I16 => format_as!(i16),
+            Primitive::U32 => format_as!(u32),
+            Primitive::I32 => format_as!(i32),
+            Primitive::U64 => format_as!(u64),
+            Primitive::I64 => format_as!(i64),
+            Primitive::F32 => format_as!(f32),
+            Primitive::F64 => format_as!(f64),
+            Primitive::Bool => match bytes[0] {
+                0 => "false".to_owned(),
+                1 => "true".to_owned(),
+                other => format!("true ({})", other),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TypeRef {
+    Primitive(Primitive),
+    /// What a `void*` points to.
+    Void,
+    /// A type in the schema, which can be found with [`find_type`].
+    Struct(&'static str),
+    /// A type from outside the schema, such as a Windows handle.
+    Opaque {
+        name: &'static str,
+        size: usize,
+    },
+    Pointer(&'static TypeRef),
+    Array(&'static TypeRef, usize),
+}
+
+impl TypeRef {
+    /// The size of the type in bytes, or `None` for `void` and types missing from the
+    /// schema.
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            TypeRef::Primitive(primitive) => Some(primitive.size()),
+            TypeRef::Void => None,
+            TypeRef::Struct(name) => find_type(name).map(|ty| ty.size),
+            TypeRef::Opaque { size, .. } => Some(*size),
+            TypeRef::Pointer(_) => Some(std::mem::size_of::<usize>()),
+            TypeRef::Array(element, len) => Some(element.size()? * len),
+        }
+    }
+
+    /// The type as it is written in the schema, such as `ZRenderCommandQueue[4]`.
+    pub fn name(&self) -> String {
+        match self {
+            TypeRef::Primitive(primitive) => primitive.name().to_owned(),
+            TypeRef::Void => "void".to_owned(),
+            TypeRef::Struct(name) | TypeRef::Opaque { name, .. } => (*name).to_owned(),
+            TypeRef::Pointer(pointee) => format!("{}*", pointee.name()),
+            TypeRef::Array(element, len) => format!("{}[{}]", element.name(), len),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub offset: usize,
+    pub ty: TypeRef,
+    pub doc: Option<&'static str>,
+}
+
+#[derive(Debug)]
+pub struct VtableSlot {
+    pub slot: usize,
+    pub name: &'static str,
+}
+
+#[derive(Debug)]
+pub struct TypeInfo {
+    pub name: &'static str,
+    pub doc: Option<&'static str>,
+    pub size: usize,
+    pub fields: &'static [FieldInfo],
+    pub vtable: &'static [VtableSlot],
+}
+
+pub fn find_type(name: &str) -> Option<&'static TypeInfo> {
+    super::types::TYPES.iter().find(|ty| ty.name == name)
+}
@@ -0,0 +1,11 @@
+//! The game types described by `schema/game.json`, generated by `build.rs`.
+
+// Not every generated type and accessor is used.
+#![allow(dead_code)]
+
+use game_struct_macro::game_struct;
+
+// The generated code names the reflection types unqualified.
+use super::reflection::*;
+
+include!(concat!(env!("OUT_DIR"), "/game_types.rs"));
@@ -1,13 +1,31 @@
 use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
 
-use crate::{detouring::prelude::*, rendering::overlay::OVERLAY};
+use crate::{
+    bindings::BINDINGS, detouring::prelude::*, rendering::overlay::OVERLAY, CONSOLE_SENDER,
+};
 
 #[detour {
     name = "ZApplicationEngineWin32::WndProc",
     pattern = "48 89 5C 24 ? 48 89 74 24 ? 48 89 7C 24 ? 55 41 54 41 55 41 56 41 57 48 8D 6C 24 ? 48 81 EC ? ? ? ? 4C 8B 65 7F",
 }]
 pub fn wnd_proc(this: usize, hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-    if OVERLAY.lock().unwrap().wnd_proc(hwnd, msg, wparam, lparam) {
+    let (handled, wants_keyboard_input) = {
+        let mut overlay = OVERLAY.lock().unwrap();
+        let handled = overlay.wnd_proc(hwnd, msg, wparam, lparam);
+        (handled, overlay.wants_keyboard_input())
+    };
+
+    if !wants_keyboard_input {
+        let line = BINDINGS
+            .lock()
+            .unwrap()
+            .command_for_message(msg, wparam, lparam);
+        if let Some(line) = line {
+            CONSOLE_SENDER.execute(&line);
+        }
+    }
+
+    if handled {
         LRESULT(0)
     } else {
         WND_PROC.call(this, hwnd, msg, wparam, lparam)
@@ -1,7 +1,15 @@
+mod bindings;
 mod console;
 mod detouring;
 mod game;
+mod logging;
+mod memory;
+mod patches;
+mod paths;
+mod rcon;
 mod rendering;
+#[cfg(feature = "debug-console")]
+mod stdin_reader;
 
 use std::{thread, time::Duration};
 
@@ -11,7 +19,7 @@ use lazy_static::lazy_static;
 use parking_lot::{Condvar, Mutex};
 use re_utilities::{module::Module, ThreadSuspender};
 
-pub use console::{MessageType, CONSOLE};
+pub use console::{MessageType, CONSOLE, CONSOLE_SENDER};
 
 #[cfg(feature = "debug-console")]
 fn alloc_console() {
@@ -45,6 +53,8 @@ fn main() -> anyhow::Result<()> {
     #[cfg(feature = "debug-console")]
     alloc_console();
 
+    logging::init()?;
+
     let mut module = Module::get_all()
         .find(|x| {
             x.filename()
@@ -74,21 +84,43 @@ fn main() -> anyhow::Result<()> {
 
     {
         let mut console = CONSOLE.lock().unwrap();
+        console.set_environment(memory::ProcessEnvironment);
         console.push_back_info("Hello from hm3-sandbox!".into());
+        logging::register_commands(&mut console);
+        rcon::register_commands(&mut console);
+        memory::register_commands(&mut console);
+        patches::register_commands(&mut console);
+        rendering::memory_viewer::register_commands(&mut console);
+        rendering::inspector::register_commands(&mut console);
+        bindings::register_commands(&mut console);
+        console.exec_autoexec();
     }
 
+    #[cfg(feature = "debug-console")]
+    let stdin_reader = stdin_reader::StdinReader::spawn();
+
     OPERATION.notify_all();
     OPERATION.wait(&mut OPERATION_MUTEX.lock());
 
-    ThreadSuspender::for_block(|| {
+    #[cfg(feature = "debug-console")]
+    stdin_reader.stop();
+    rcon::stop();
+
+    // Patches are reverted while the game's threads are suspended so that none of them is
+    // running patched code, but the result is only logged once they have been resumed.
+    let reverted = ThreadSuspender::for_block(|| {
         for hook_library in &mut loaded_libraries {
             hook_library.set_enabled(false)?;
         }
-        Ok(())
+        Ok(patches::revert_all())
     })?;
+    match reverted {
+        Ok(0) => {}
+        Ok(count) => log::info!("Reverted {} memory patches", count),
+        Err(e) => log::error!("{}", e),
+    }
 
-    #[cfg(feature = "debug-console")]
-    println!("Delaying exit...");
+    log::info!("Delaying exit...");
     thread::sleep(Duration::new(1, 0));
 
     #[cfg(feature = "debug-console")]
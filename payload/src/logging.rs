@@ -0,0 +1,195 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use parking_lot::{Mutex, RwLock};
+
+use crate::{
+    console::{ArgType, CommandSignature, Console},
+    MessageType, CONSOLE_SENDER,
+};
+
+const LEVEL_NAMES: &[&str] = &["off", "error", "warn", "info", "debug", "trace", "default"];
+
+#[cfg(feature = "debug-logging")]
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Debug;
+#[cfg(not(feature = "debug-logging"))]
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// The maximum level to log for each target, where a target also covers the modules
+/// beneath it (`payload::game` covers `payload::game::zrender`).
+struct Levels {
+    default: LevelFilter,
+    targets: BTreeMap<String, LevelFilter>,
+}
+
+impl Levels {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.targets.values().copied().fold(self.default, Ord::max)
+    }
+}
+
+lazy_static! {
+    static ref LEVELS: RwLock<Levels> = RwLock::new(Levels {
+        default: DEFAULT_LEVEL,
+        targets: BTreeMap::new(),
+    });
+    /// Every target that has logged so far, for completion.
+    static ref TARGETS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+}
+
+struct ConsoleLogger;
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+/// Returns the console channel for `target`: the top-level module for targets within the
+/// payload, or the target itself for anything else.
+fn channel_for(target: &str) -> &str {
+    let mut parts = target.split("::");
+    match parts.next() {
+        Some(env!("CARGO_CRATE_NAME")) => parts.next().unwrap_or(target),
+        _ => target,
+    }
+}
+
+fn message_type_for(level: Level) -> MessageType {
+    match level {
+        Level::Error => MessageType::Error,
+        Level::Warn => MessageType::Misc((255, 178, 56)),
+        Level::Info => MessageType::Info,
+        Level::Debug => MessageType::Misc((170, 170, 170)),
+        Level::Trace => MessageType::Misc((120, 120, 120)),
+    }
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= LEVELS.read().level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let target = record.target();
+        if !TARGETS.lock().contains(target) {
+            TARGETS.lock().insert(target.to_owned());
+        }
+
+        let text = match record.level() {
+            Level::Info | Level::Error => record.args().to_string(),
+            level => format!("[{}] {}", level, record.args()),
+        };
+
+        #[cfg(feature = "debug-console")]
+        println!(
+            "[{}] {}: {}",
+            record.level(),
+            target,
+            crate::console::strip_markup(&record.args().to_string())
+        );
+
+        CONSOLE_SENDER.push_back_channel_message(
+            channel_for(target),
+            text,
+            message_type_for(record.level()),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger that forwards `log` records from any thread into the console.
+pub fn init() -> anyhow::Result<()> {
+    log::set_logger(&LOGGER).map_err(|err| anyhow::anyhow!("{}", err))?;
+    log::set_max_level(LEVELS.read().max_level());
+    Ok(())
+}
+
+fn parse_level(name: &str) -> Option<LevelFilter> {
+    match name {
+        "default" => None,
+        name => name.parse().ok(),
+    }
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command_with_completer(
+        CommandSignature::new("loglevel", "Shows or sets the log level for a target.")
+            .optional_arg(
+                "target",
+                ArgType::String,
+                "",
+                "A module path like `payload::rendering`, or `*` for every target.",
+            )
+            .optional_arg(
+                "level",
+                ArgType::Enum(LEVEL_NAMES),
+                "",
+                "The most verbose level to log; `default` removes the target's override.",
+            ),
+        |console, args| {
+            let target = args.string("target")?;
+            let level = args.string("level")?;
+            let mut levels = LEVELS.write();
+
+            match (target, level) {
+                ("", _) => {
+                    let mut lines = vec![format!("* = {}", levels.default)];
+                    lines.extend(
+                        levels
+                            .targets
+                            .iter()
+                            .map(|(target, level)| format!("{} = {}", target, level)),
+                    );
+                    for line in lines {
+                        console.push_back_info(line);
+                    }
+                }
+                (target, "") => {
+                    let level = levels.level_for(target);
+                    console.push_back_info(format!("{} = {}", target, level));
+                }
+                ("*", level) => {
+                    levels.default = parse_level(level)
+                        .ok_or_else(|| anyhow::anyhow!("`*` has no default to return to."))?;
+                }
+                (target, level) => match parse_level(level) {
+                    Some(level) => {
+                        levels.targets.insert(target.to_owned(), level);
+                    }
+                    None => {
+                        levels.targets.remove(target);
+                    }
+                },
+            }
+
+            log::set_max_level(levels.max_level());
+            Ok(())
+        },
+        |_, args| match args {
+            [] => {
+                let mut targets: Vec<_> = TARGETS.lock().iter().cloned().collect();
+                targets.extend(LEVELS.read().targets.keys().cloned());
+                targets.push("*".into());
+                targets
+            }
+            [_] => LEVEL_NAMES.iter().map(|l| l.to_string()).collect(),
+            _ => vec![],
+        },
+    );
+}
@@ -0,0 +1,359 @@
+use std::{collections::BTreeMap, ffi::c_void, fmt};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use windows::{
+    core::PCSTR,
+    Win32::System::{
+        Diagnostics::Debug::{FlushInstructionCache, ReadProcessMemory, WriteProcessMemory},
+        LibraryLoader::GetModuleHandleA,
+        Memory::{
+            VirtualProtect, VirtualQuery, MEMORY_BASIC_INFORMATION, MEM_COMMIT, PAGE_EXECUTE_READ,
+            PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS,
+            PAGE_PROTECTION_FLAGS, PAGE_READONLY, PAGE_READWRITE, PAGE_WRITECOPY,
+        },
+        Threading::GetCurrentProcess,
+    },
+};
+
+use crate::console::{ArgType, CommandSignature, Console, Environment, Span, Table};
+
+lazy_static! {
+    /// Addresses found at runtime, such as by signature scans, that expressions can name.
+    static ref SYMBOLS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+}
+
+/// Returns the end of the region of pages containing `address`, and whether the pages are
+/// committed and readable, or `None` if the address is outside the address space.
+fn query_region(address: usize) -> Option<(usize, bool)> {
+    let mut info = MEMORY_BASIC_INFORMATION::default();
+    let size = std::mem::size_of::<MEMORY_BASIC_INFORMATION>();
+    if unsafe { VirtualQuery(address as *const c_void, &mut info, size) } == 0 {
+        return None;
+    }
+
+    let readable = PAGE_READONLY.0
+        | PAGE_READWRITE.0
+        | PAGE_WRITECOPY.0
+        | PAGE_EXECUTE_READ.0
+        | PAGE_EXECUTE_READWRITE.0
+        | PAGE_EXECUTE_WRITECOPY.0;
+    let protect = info.Protect.0;
+    let is_readable = info.State == MEM_COMMIT
+        && protect & readable != 0
+        && protect & (PAGE_NOACCESS.0 | PAGE_GUARD.0) == 0;
+    Some((info.BaseAddress as usize + info.RegionSize, is_readable))
+}
+
+/// Returns whether every page from `address` to `address + len` can be read.
+pub fn is_readable(address: usize, len: usize) -> bool {
+    let end = match address.checked_add(len) {
+        Some(end) => end,
+        None => return false,
+    };
+    let mut current = address;
+    while current < end {
+        match query_region(current) {
+            Some((region_end, true)) if region_end > current => current = region_end,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Reads `len` bytes from `address`, with `None` for each byte whose page cannot be read.
+pub fn read_partial(address: usize, len: usize) -> Vec<Option<u8>> {
+    let mut bytes = vec![None; len];
+    let mut offset = 0;
+    while offset < len {
+        let current = match address.checked_add(offset) {
+            Some(current) => current,
+            None => break,
+        };
+        let (region_end, readable) = match query_region(current) {
+            Some((region_end, readable)) if region_end > current => (region_end, readable),
+            _ => break,
+        };
+
+        let chunk = (region_end - current).min(len - offset);
+        let mut buffer = vec![0; chunk];
+        if readable && read(current, &mut buffer).is_ok() {
+            for (byte, value) in bytes[offset..offset + chunk].iter_mut().zip(buffer) {
+                *byte = Some(value);
+            }
+        }
+        offset += chunk;
+    }
+    bytes
+}
+
+/// Copies the memory at `address` into `buffer`, failing instead of crashing if any of it
+/// is not in readable pages.
+pub fn read(address: usize, buffer: &mut [u8]) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        is_readable(address, buffer.len()),
+        "Cannot read {} bytes at 0x{:X}.",
+        buffer.len(),
+        address
+    );
+
+    // The pages could still be freed by another thread before they are read, which this
+    // fails on rather than crashing.
+    let mut read = 0;
+    let ok = unsafe {
+        ReadProcessMemory(
+            GetCurrentProcess(),
+            address as *const c_void,
+            buffer.as_mut_ptr() as *mut c_void,
+            buffer.len(),
+            &mut read,
+        )
+    };
+    anyhow::ensure!(
+        ok.as_bool() && read == buffer.len(),
+        "Cannot read {} bytes at 0x{:X}.",
+        buffer.len(),
+        address
+    );
+    Ok(())
+}
+
+pub fn read_u64(address: usize) -> anyhow::Result<u64> {
+    let mut bytes = [0; 8];
+    read(address, &mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Copies `bytes` over the memory at `address`, making its pages writable while doing so,
+/// so that code and read-only data can be patched. Each region of pages gets its own
+/// protection back afterwards, and if any of them cannot be written, the ones already
+/// written are restored.
+pub fn write(address: usize, bytes: &[u8]) -> anyhow::Result<()> {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    let mut original = vec![0; bytes.len()];
+    read(address, &mut original)?;
+
+    // `read` checked that every region is readable, so each of them ends past where it
+    // is queried.
+    let mut chunks = vec![];
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let current = address + offset;
+        let region_end = query_region(current).map_or(usize::MAX, |(end, _)| end);
+        let end = (offset + (region_end - current)).min(bytes.len());
+        chunks.push(offset..end);
+        offset = end;
+    }
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        if let Err(e) = write_region(address + chunk.start, &bytes[chunk.clone()]) {
+            for written in &chunks[..index] {
+                let _ = write_region(address + written.start, &original[written.clone()]);
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Writes `bytes` at `address`, which must all be in one region of pages, so that the
+/// region's protection is the one restored.
+fn write_region(address: usize, bytes: &[u8]) -> anyhow::Result<()> {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    let target = address as *const c_void;
+    let mut protect = PAGE_PROTECTION_FLAGS::default();
+    let ok = unsafe { VirtualProtect(target, bytes.len(), PAGE_EXECUTE_READWRITE, &mut protect) };
+    anyhow::ensure!(
+        ok.as_bool(),
+        "Cannot make {} bytes at 0x{:X} writable.",
+        bytes.len(),
+        address
+    );
+
+    let mut written = 0;
+    let ok = unsafe {
+        let ok = WriteProcessMemory(
+            GetCurrentProcess(),
+            target,
+            bytes.as_ptr() as *const c_void,
+            bytes.len(),
+            &mut written,
+        );
+        VirtualProtect(target, bytes.len(), protect, &mut protect);
+        FlushInstructionCache(GetCurrentProcess(), target, bytes.len());
+        ok
+    };
+    anyhow::ensure!(
+        ok.as_bool() && written == bytes.len(),
+        "Cannot write {} bytes at 0x{:X}.",
+        bytes.len(),
+        address
+    );
+    Ok(())
+}
+
+/// The base address of the loaded module called `name`, such as `HITMAN3.exe`.
+pub fn module_base(name: &str) -> Option<usize> {
+    if name.contains('\0') {
+        return None;
+    }
+    let name = format!("{}\0", name);
+    let module = unsafe { GetModuleHandleA(PCSTR(name.as_ptr())) };
+    (module.0 != 0).then_some(module.0 as usize)
+}
+
+/// Lets expressions refer to `address` as `name`, such as `RENDER_MANAGER`.
+pub fn add_symbol(name: &str, address: usize) {
+    SYMBOLS.lock().insert(name.to_owned(), address);
+}
+
+/// Evaluates console expressions against the game's process, with the symbols added by
+/// [`add_symbol`] and loaded modules as symbols.
+pub struct ProcessEnvironment;
+
+impl Environment for ProcessEnvironment {
+    fn symbol(&self, name: &str) -> Option<u64> {
+        let symbol = SYMBOLS.lock().get(name).copied();
+        symbol
+            .or_else(|| module_base(name))
+            .map(|address| address as u64)
+    }
+
+    fn read_pointer(&self, address: u64) -> anyhow::Result<u64> {
+        read_u64(address as usize)
+    }
+}
+
+/// Where a pointer chain could not be followed.
+#[derive(Debug)]
+pub struct ChainError {
+    /// The position in the chain, counting from 1, of the offset that could not be added
+    /// because the pointer before it could not be read.
+    pub step: usize,
+    /// The address that could not be read.
+    pub address: usize,
+    /// The addresses resolved before the chain broke.
+    pub resolved: Vec<usize>,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The chain broke at offset {}: cannot read the pointer at 0x{:X}.",
+            self.step, self.address
+        )
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// Follows a pointer chain the way `[[base + a] + b] + c` would, returning the address
+/// reached at each offset. The last address is where the chain ends, and is not read.
+pub fn follow_chain(base: usize, offsets: &[usize]) -> Result<Vec<usize>, ChainError> {
+    let (first, rest) = match offsets.split_first() {
+        Some(split) => split,
+        None => return Ok(vec![base]),
+    };
+
+    let mut resolved = vec![base.wrapping_add(*first)];
+    for (index, offset) in rest.iter().enumerate() {
+        let address = resolved[resolved.len() - 1];
+        match read_u64(address) {
+            Ok(pointer) => resolved.push((pointer as usize).wrapping_add(*offset)),
+            Err(_) => {
+                return Err(ChainError {
+                    step: index + 2,
+                    address,
+                    resolved,
+                })
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Returns the address at the end of a pointer chain, such as
+/// `resolve_chain(render_manager, &[0x14178, 0x420])` for where the render device keeps its
+/// D3D12 device.
+pub fn resolve_chain(base: usize, offsets: &[usize]) -> Result<usize, ChainError> {
+    follow_chain(base, offsets).map(|resolved| resolved[resolved.len() - 1])
+}
+
+/// Splits offsets given as `0x14178, 0x420` or `0x14178 0x420` into expressions.
+fn split_offsets(text: &str) -> Vec<&str> {
+    if text.contains(',') {
+        text.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect()
+    } else {
+        text.split_whitespace().collect()
+    }
+}
+
+fn chain_table(base: usize, offsets: &[usize], resolved: &[usize]) -> Table {
+    let mut table = Table::new(&["Offset", "Address", "Value"]);
+    let address_span =
+        |address: usize| Span::command(format!("0x{:X}", address), format!("mem 0x{:X}", address));
+
+    table.push_row([Span::from("base"), address_span(base)]);
+    for (offset, address) in offsets.iter().zip(resolved) {
+        let value = match read_u64(*address) {
+            Ok(value) => address_span(value as usize),
+            Err(_) => Span::from("??"),
+        };
+        table.push_row([
+            Span::from(format!("+0x{:X}", offset)),
+            address_span(*address),
+            value,
+        ]);
+    }
+    table
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command(
+        CommandSignature::new(
+            "ptr",
+            "Follows a pointer chain from a base address, showing each address it passes through.",
+        )
+        .arg(
+            "base",
+            ArgType::Address,
+            "The address to start from, such as `HITMAN3.exe+0x1A2B3C` or `RENDER_MANAGER`.",
+        )
+        .rest(
+            "offsets",
+            ArgType::String,
+            "The offsets to add, reading a pointer before each but the first, such as `0x14178, 0x420`.",
+        ),
+        |console, args| {
+            let base = args.address("base")?;
+            let text: Vec<_> = args.rest().iter().map(|v| v.to_string()).collect();
+            let offsets = split_offsets(&text.join(" "))
+                .into_iter()
+                .map(|offset| console.evaluate(offset).map(|offset| offset as usize))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            match follow_chain(base, &offsets) {
+                Ok(resolved) => {
+                    console.push_back_table(chain_table(base, &offsets, &resolved));
+                    console.push_back_info(format!("= 0x{:X}", resolved[resolved.len() - 1]));
+                }
+                Err(err) => {
+                    console.push_back_table(chain_table(base, &offsets, &err.resolved));
+                    console.push_back_error(err.to_string());
+                }
+            }
+            Ok(())
+        },
+    );
+}
@@ -0,0 +1,341 @@
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::{
+    console::{ArgType, CommandSignature, Console, Span, Table},
+    memory,
+};
+
+/// The most bytes `nop` will overwrite at once, to catch typos like `nop 0x1000 0x1000`.
+const MAX_NOP_LEN: i64 = 4096;
+
+const VALUE_TYPES: &[&str] = &[
+    "u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "f32", "f64",
+];
+
+struct Patch {
+    address: usize,
+    original: Vec<u8>,
+    bytes: Vec<u8>,
+    /// The command that made the patch.
+    description: String,
+}
+
+impl Patch {
+    fn revert(&self) -> anyhow::Result<()> {
+        memory::write(self.address, &self.original)
+    }
+}
+
+lazy_static! {
+    /// Every patch that has not been reverted, oldest first. Patches are reverted newest
+    /// first so that overlapping patches restore the bytes from before any of them.
+    static ref PATCHES: Mutex<Vec<Patch>> = Mutex::new(vec![]);
+}
+
+/// Writes `bytes` at `address`, remembering the bytes they replace so that the patch can
+/// be undone.
+pub fn apply(address: usize, bytes: Vec<u8>, description: String) -> anyhow::Result<()> {
+    let mut original = vec![0; bytes.len()];
+    memory::read(address, &mut original)?;
+    memory::write(address, &bytes)?;
+    PATCHES.lock().push(Patch {
+        address,
+        original,
+        bytes,
+        description,
+    });
+    Ok(())
+}
+
+/// Reverts the newest patch, returning what made it, or `None` if there are no patches.
+pub fn undo() -> anyhow::Result<Option<String>> {
+    let mut patches = PATCHES.lock();
+    let patch = match patches.last() {
+        Some(patch) => patch,
+        None => return Ok(None),
+    };
+    patch.revert()?;
+    Ok(patches.pop().map(|patch| patch.description))
+}
+
+/// Reverts every patch, returning how many were reverted. Patches that cannot be reverted
+/// are kept so that they can be tried again.
+pub fn revert_all() -> anyhow::Result<usize> {
+    let mut patches = PATCHES.lock();
+    let mut reverted = 0;
+    let mut failed = vec![];
+    while let Some(patch) = patches.pop() {
+        match patch.revert() {
+            Ok(()) => reverted += 1,
+            Err(e) => failed.push((patch, e)),
+        }
+    }
+
+    if failed.is_empty() {
+        return Ok(reverted);
+    }
+    let errors: Vec<_> = failed
+        .iter()
+        .map(|(patch, e)| format!("`{}`: {}", patch.description, e))
+        .collect();
+    patches.extend(failed.into_iter().rev().map(|(patch, _)| patch));
+    anyhow::bail!(
+        "Reverted {} patches, but could not revert {}",
+        reverted,
+        errors.join(" ")
+    )
+}
+
+/// Parses bytes written as `90 90`, `9090` or `0x90, 0x90`. Each word separated by
+/// whitespace or commas is one byte of one or two digits, or, without a `0x` prefix, a run
+/// of two-digit bytes.
+fn parse_bytes(text: &str) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+        if word.is_empty() {
+            continue;
+        }
+        let (prefixed, digits) = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+            Some(digits) => (true, digits),
+            None => (false, word),
+        };
+        anyhow::ensure!(
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()),
+            "`{}` is not a hexadecimal byte.",
+            word
+        );
+        match digits.len() {
+            1 | 2 => bytes.push(u8::from_str_radix(digits, 16)?),
+            _ if prefixed => anyhow::bail!("`{}` is more than one byte.", word),
+            len if len % 2 != 0 => {
+                anyhow::bail!("`{}` has an odd number of hexadecimal digits.", word)
+            }
+            len => {
+                for i in (0..len).step_by(2) {
+                    bytes.push(u8::from_str_radix(&digits[i..i + 2], 16)?);
+                }
+            }
+        }
+    }
+    anyhow::ensure!(!bytes.is_empty(), "There are no bytes to write.");
+    Ok(bytes)
+}
+
+/// Returns the little-endian bytes of `value` as an integer of `size` bytes, which it must
+/// fit in as either a signed or an unsigned integer.
+fn int_bytes(value: u64, size: usize) -> anyhow::Result<Vec<u8>> {
+    let bits = size as u32 * 8;
+    let signed = value as i64;
+    let fits = bits == 64 || value < 1 << bits || (signed < 0 && signed >= -(1 << (bits - 1)));
+    anyhow::ensure!(fits, "{} does not fit in {} bytes.", signed, size);
+    Ok(value.to_le_bytes()[..size].to_vec())
+}
+
+/// Returns the bytes of `text` as the type called `ty`, evaluating integers as
+/// expressions.
+fn value_bytes(console: &Console, ty: &str, text: &str) -> anyhow::Result<Vec<u8>> {
+    let float_error = |_| anyhow::anyhow!("`{}` is not a number.", text);
+    match ty {
+        "f32" => Ok(text
+            .parse::<f32>()
+            .map_err(float_error)?
+            .to_le_bytes()
+            .to_vec()),
+        "f64" => Ok(text
+            .parse::<f64>()
+            .map_err(float_error)?
+            .to_le_bytes()
+            .to_vec()),
+        _ => {
+            let size = match ty {
+                "u8" | "i8" => 1,
+                "u16" | "i16" => 2,
+                "u32" | "i32" => 4,
+                _ => 8,
+            };
+            int_bytes(console.evaluate(text)?, size)
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn apply_and_report(
+    console: &mut Console,
+    address: usize,
+    bytes: Vec<u8>,
+    description: String,
+) -> anyhow::Result<()> {
+    let len = bytes.len();
+    apply(address, bytes, description)?;
+    console.push_back_info(format!(
+        "Patched {} bytes at 0x{:X}. Use `undo` to revert it.",
+        len, address
+    ));
+    Ok(())
+}
+
+fn patch_table(patches: &[Patch]) -> Table {
+    let mut table = Table::new(&["Address", "Original", "Patched", "Command"]);
+    for patch in patches {
+        table.push_row([
+            Span::command(
+                format!("0x{:X}", patch.address),
+                format!("mem 0x{:X}", patch.address),
+            ),
+            Span::from(hex(&patch.original)),
+            Span::from(hex(&patch.bytes)),
+            Span::from(patch.description.clone()),
+        ]);
+    }
+    table
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command(
+        CommandSignature::new(
+            "write",
+            "Writes a value to memory, making the memory writable if needed.",
+        )
+        .arg("address", ArgType::Address, "The address to write to.")
+        .arg("type", ArgType::Enum(VALUE_TYPES), "The type of the value.")
+        .arg(
+            "value",
+            ArgType::String,
+            "The value, which for integers can be an expression such as `-1` or `0x7F`.",
+        ),
+        |console, args| {
+            let address = args.address("address")?;
+            let ty = args.string("type")?;
+            let value = args.string("value")?;
+            let bytes = value_bytes(console, ty, value)?;
+            let description = format!("write 0x{:X} {} {}", address, ty, value);
+            apply_and_report(console, address, bytes, description)
+        },
+    );
+
+    console.register_command(
+        CommandSignature::new("patch", "Writes bytes to memory, such as to change code.")
+            .arg("address", ArgType::Address, "The address to write to.")
+            .rest(
+                "bytes",
+                ArgType::String,
+                "The bytes in hexadecimal, such as `90 90` or `EB05`.",
+            ),
+        |console, args| {
+            let address = args.address("address")?;
+            let text: Vec<_> = args.rest().iter().map(|v| v.to_string()).collect();
+            let bytes = parse_bytes(&text.join(" "))?;
+            let description = format!("patch 0x{:X} {}", address, hex(&bytes));
+            apply_and_report(console, address, bytes, description)
+        },
+    );
+
+    console.register_command(
+        CommandSignature::new("nop", "Replaces code with NOP instructions.")
+            .arg("address", ArgType::Address, "The address of the code.")
+            .arg("len", ArgType::Int, "How many bytes to replace."),
+        |console, args| {
+            let address = args.address("address")?;
+            let len = args.int("len")?;
+            anyhow::ensure!(
+                (1..=MAX_NOP_LEN).contains(&len),
+                "The length must be from 1 to {}.",
+                MAX_NOP_LEN
+            );
+            let description = format!("nop 0x{:X} {}", address, len);
+            apply_and_report(console, address, vec![0x90; len as usize], description)
+        },
+    );
+
+    console.register_command(
+        CommandSignature::new("undo", "Reverts the most recent memory patch."),
+        |console, _| {
+            match undo()? {
+                Some(description) => console.push_back_info(format!("Reverted `{}`.", description)),
+                None => console.push_back_info("There are no patches to revert.".into()),
+            }
+            Ok(())
+        },
+    );
+
+    console.register_command(
+        CommandSignature::new("revert-all", "Reverts every memory patch."),
+        |console, _| {
+            let reverted = revert_all()?;
+            console.push_back_info(format!("Reverted {} patches.", reverted));
+            Ok(())
+        },
+    );
+
+    console.register_command(
+        CommandSignature::new(
+            "patches",
+            "Lists the memory patches that have not been reverted, oldest first.",
+        ),
+        |console, _| {
+            let patches = PATCHES.lock();
+            if patches.is_empty() {
+                console.push_back_info("There are no patches.".into());
+            } else {
+                console.push_back_table(patch_table(&patches));
+            }
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bytes() {
+        assert_eq!(parse_bytes("90 90").unwrap(), [0x90, 0x90]);
+        assert_eq!(parse_bytes("9090").unwrap(), [0x90, 0x90]);
+        assert_eq!(parse_bytes("0x90, 0x90").unwrap(), [0x90, 0x90]);
+        assert_eq!(parse_bytes("EB05 cc").unwrap(), [0xEB, 0x05, 0xCC]);
+    }
+
+    #[test]
+    fn keeps_word_boundaries() {
+        assert_eq!(parse_bytes("0x9, 0x0").unwrap(), [0x09, 0x00]);
+        assert_eq!(parse_bytes("9 0 9 0").unwrap(), [0x09, 0x00, 0x09, 0x00]);
+        assert_eq!(parse_bytes("0x9 0x90").unwrap(), [0x09, 0x90]);
+    }
+
+    #[test]
+    fn rejects_malformed_bytes() {
+        assert!(parse_bytes("").is_err());
+        assert!(parse_bytes(" , ").is_err());
+        assert!(parse_bytes("909").is_err());
+        assert!(parse_bytes("0x9090").is_err());
+        assert!(parse_bytes("0x").is_err());
+        assert!(parse_bytes("GG").is_err());
+    }
+
+    #[test]
+    fn converts_ints_that_fit() {
+        assert_eq!(int_bytes(255, 1).unwrap(), [0xFF]);
+        assert_eq!(int_bytes(-128_i64 as u64, 1).unwrap(), [0x80]);
+        assert_eq!(int_bytes(-1_i64 as u64, 1).unwrap(), [0xFF]);
+        assert_eq!(int_bytes(0x1234, 2).unwrap(), [0x34, 0x12]);
+        assert_eq!(int_bytes(-1_i64 as u64, 4).unwrap(), [0xFF; 4]);
+        assert_eq!(int_bytes(u64::MAX, 8).unwrap(), [0xFF; 8]);
+    }
+
+    #[test]
+    fn rejects_ints_that_do_not_fit() {
+        assert!(int_bytes(256, 1).is_err());
+        assert!(int_bytes(-129_i64 as u64, 1).is_err());
+        assert!(int_bytes(0x1_0000, 2).is_err());
+        assert!(int_bytes(-0x8001_i64 as u64, 2).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use windows::{
+    core::{PCSTR, PSTR},
+    Win32::{
+        Foundation::HINSTANCE,
+        System::LibraryLoader::{
+            GetModuleFileNameA, GetModuleHandleExA, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+            GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+        },
+    },
+};
+
+/// The directory that `payload.dll` was loaded from.
+pub fn payload_directory() -> anyhow::Result<PathBuf> {
+    unsafe {
+        let mut module = HINSTANCE::default();
+        GetModuleHandleExA(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            PCSTR(payload_directory as *const u8),
+            &mut module,
+        )
+        .ok()
+        .context("Failed to get payload module handle")?;
+
+        let mut buffer = [0u8; 260];
+        let len =
+            GetModuleFileNameA(module, PSTR(buffer.as_mut_ptr()), buffer.len() as u32) as usize;
+        anyhow::ensure!(len != 0, "Failed to get payload module filename");
+
+        let path = PathBuf::from(String::from_utf8_lossy(&buffer[..len]).into_owned());
+        path.parent()
+            .map(|p| p.to_path_buf())
+            .context("Payload module has no parent directory")
+    }
+}
+
+/// A file that lives next to `payload.dll`.
+pub fn payload_file(filename: &str) -> anyhow::Result<PathBuf> {
+    Ok(payload_directory()?.join(filename))
+}
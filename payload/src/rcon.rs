@@ -0,0 +1,451 @@
+//! A remote console for scripting the sandbox from other programs.
+//!
+//! Clients connect to `127.0.0.1:<rcon_port>` and send one command line per line. The first
+//! line must be the token, which is the `rcon_token` variable or, if that is empty, the
+//! contents of `rcon_token.txt` next to payload.dll. The server replies with one JSON object
+//! per line:
+//!
+//! ```text
+//! {"type":"auth","ok":true}
+//! {"type":"message","time":"2022-01-01T12:00:00+00:00","channel":"console","kind":"Info","text":"hi"}
+//! {"type":"done","command":"echo hi"}
+//! ```
+//!
+//! Each command line is answered with the messages it produced, followed by `done`. A
+//! rejected token is answered with `{"type":"auth","ok":false,"error":...}` and the
+//! connection is closed.
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+
+use crate::{
+    console::{ArgType, CommandSignature, Console, ConsoleSender, Cvar, Message},
+    MessageType, CONSOLE, CONSOLE_SENDER,
+};
+
+pub const TOKEN_FILENAME: &str = "rcon_token.txt";
+const CHANNEL: &str = "rcon";
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The number of random bytes in a generated token.
+const TOKEN_BYTES: usize = 16;
+
+/// Listens for remote console clients on a background thread.
+pub struct Server {
+    address: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Server {
+    /// Starts listening on `address`. Clients' commands are sent to `console` through
+    /// `sender`, as with [`ConsoleSender::run_and_wait`].
+    pub fn start(
+        console: &'static Mutex<Console>,
+        sender: ConsoleSender,
+        address: SocketAddr,
+        token: String,
+    ) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+
+        let stopping = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stopping = stopping.clone();
+            move || accept_connections(listener, console, sender, Arc::from(token), &stopping)
+        });
+
+        Ok(Self {
+            address,
+            stopping,
+            thread,
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Stops accepting clients and disconnects the connected ones without waiting for them,
+    /// so that it is safe to call from a command run by a client.
+    pub fn shutdown(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    /// Shuts the server down and waits for all of its threads to finish.
+    pub fn join(self) {
+        self.shutdown();
+        let _ = self.thread.join();
+    }
+}
+
+fn accept_connections(
+    listener: TcpListener,
+    console: &'static Mutex<Console>,
+    sender: ConsoleSender,
+    token: Arc<str>,
+    stopping: &AtomicBool,
+) {
+    let mut clients: Vec<(TcpStream, JoinHandle<()>)> = vec![];
+    while !stopping.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let client = stream
+                    .set_nonblocking(false)
+                    .and_then(|_| stream.try_clone());
+                match client {
+                    Ok(client) => {
+                        let sender = sender.clone();
+                        let token = token.clone();
+                        let thread = thread::spawn(move || {
+                            if let Err(err) = serve(console, &sender, stream, &token) {
+                                log::warn!("RCON client {} disconnected: {}", peer, err);
+                            }
+                        });
+                        clients.push((client, thread));
+                    }
+                    Err(err) => log::warn!("Failed to accept RCON client {}: {}", peer, err),
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_POLL_INTERVAL)
+            }
+            Err(err) => {
+                log::error!("RCON server stopped: {}", err);
+                break;
+            }
+        }
+
+        clients.retain(|(_, thread)| !thread.is_finished());
+    }
+
+    for (client, _) in &clients {
+        let _ = client.shutdown(Shutdown::Both);
+    }
+    for (_, thread) in clients {
+        let _ = thread.join();
+    }
+}
+
+fn write_json(writer: &mut impl Write, value: Value) -> io::Result<()> {
+    writeln!(writer, "{}", value)
+}
+
+fn message_json(message: &Message) -> Value {
+    let kind = match message.message_type() {
+        MessageType::Command => "Command",
+        MessageType::Info => "Info",
+        MessageType::Error => "Error",
+        MessageType::Misc(_) => "Misc",
+    };
+    json!({
+        "type": "message",
+        "time": message.time().to_rfc3339(),
+        "channel": message.channel(),
+        "kind": kind,
+        "text": message.text(),
+    })
+}
+
+/// Compares `a` and `b` in a time that does not depend on where they differ, so that a
+/// client cannot guess the token a byte at a time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn serve(
+    console: &Mutex<Console>,
+    sender: &ConsoleSender,
+    stream: TcpStream,
+    token: &str,
+) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if !constant_time_eq(
+        line.trim_end_matches(&['\r', '\n'][..]).as_bytes(),
+        token.as_bytes(),
+    ) {
+        write_json(
+            &mut writer,
+            json!({ "type": "auth", "ok": false, "error": "Invalid token." }),
+        )?;
+        writer.flush()?;
+        anyhow::bail!("invalid token");
+    }
+    write_json(&mut writer, json!({ "type": "auth", "ok": true }))?;
+    writer.flush()?;
+    reader.get_ref().set_read_timeout(None)?;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // The command runs with everything else sent to the console, rather than on this
+        // thread while the overlay may be drawing it.
+        let replies = sender.run_and_wait(console, {
+            let line = line.to_owned();
+            move |console| {
+                console.push_back_channel_message(
+                    CHANNEL,
+                    format!("> {}", line),
+                    MessageType::Command,
+                );
+
+                let count = console.message_count();
+                console.execute(&line);
+                let replies: Vec<_> = console.messages_since(count).map(message_json).collect();
+                replies
+            }
+        })?;
+        for reply in replies {
+            write_json(&mut writer, reply)?;
+        }
+        write_json(&mut writer, json!({ "type": "done", "command": line }))?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Returns a token made from the operating system's secure randomness, as hex.
+fn generate_token() -> anyhow::Result<String> {
+    let mut bytes = [0; TOKEN_BYTES];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Uses the `rcon_token` variable if it is set, and otherwise generates a token and writes
+/// it to [`TOKEN_FILENAME`] for clients to read.
+fn token(console: &Console) -> anyhow::Result<String> {
+    match console.cvars().string("rcon_token") {
+        Some(token) if !token.is_empty() => Ok(token.to_owned()),
+        _ => {
+            let token = generate_token()?;
+            fs::write(crate::paths::payload_file(TOKEN_FILENAME)?, &token)?;
+            Ok(token)
+        }
+    }
+}
+
+lazy_static! {
+    static ref SERVER: Mutex<Option<Server>> = Mutex::new(None);
+    /// Servers stopped by a command, which may have been run by one of their own clients,
+    /// and so could not be waited for then.
+    static ref STOPPED: Mutex<Vec<Server>> = Mutex::new(vec![]);
+}
+
+/// Stops the server, if it is running, and waits for every server thread to finish.
+pub fn stop() {
+    let servers: Vec<_> = SERVER
+        .lock()
+        .unwrap()
+        .take()
+        .into_iter()
+        .chain(STOPPED.lock().unwrap().drain(..))
+        .collect();
+    for server in servers {
+        server.join();
+    }
+}
+
+pub fn register_commands(console: &mut Console) {
+    let cvars = console.cvars_mut();
+    let results = [
+        cvars.register(
+            Cvar::new(
+                "rcon_port",
+                "The localhost port the remote console listens on.",
+                27015_i64,
+            )
+            .range(1.0, 65535.0),
+        ),
+        cvars.register(Cvar::new(
+            "rcon_token",
+            "The token remote console clients must send first. If empty, a token is generated and written to rcon_token.txt.",
+            "",
+        )),
+    ];
+    for result in results {
+        if let Err(err) = result {
+            console.push_back_error(format!("Failed to register RCON variables: {}", err));
+        }
+    }
+
+    console.register_command(
+        CommandSignature::new("rcon", "Starts or stops the remote console server.").optional_arg(
+            "action",
+            ArgType::Enum(&["start", "stop", "status"]),
+            "status",
+            "What to do with the server.",
+        ),
+        |console, args| {
+            let mut server = SERVER.lock().unwrap();
+            match (args.string("action")?, server.as_ref()) {
+                ("start", Some(running)) => {
+                    anyhow::bail!(
+                        "The RCON server is already listening on {}.",
+                        running.address()
+                    )
+                }
+                ("start", None) => {
+                    let port = console.cvars().int("rcon_port").unwrap_or(27015) as u16;
+                    let address = SocketAddr::from(([127, 0, 0, 1], port));
+                    let started =
+                        Server::start(&CONSOLE, CONSOLE_SENDER.clone(), address, token(console)?)?;
+                    console.push_back_info(format!(
+                        "The RCON server is listening on {}.",
+                        started.address()
+                    ));
+                    *server = Some(started);
+                }
+                ("stop", _) => {
+                    let stopped = server
+                        .take()
+                        .ok_or_else(|| anyhow::anyhow!("The RCON server is not running."))?;
+                    stopped.shutdown();
+                    STOPPED.lock().unwrap().push(stopped);
+                }
+                (_, Some(running)) => console.push_back_info(format!(
+                    "The RCON server is listening on {}.",
+                    running.address()
+                )),
+                (_, None) => console.push_back_info("The RCON server is not running.".into()),
+            }
+            Ok(())
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for an external tool talking to the server.
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(server: &Server, token: &str) -> (Self, Value) {
+            let writer = TcpStream::connect(server.address()).unwrap();
+            let mut client = Client {
+                reader: BufReader::new(writer.try_clone().unwrap()),
+                writer,
+            };
+            client.send(token);
+            let reply = client.receive().unwrap();
+            (client, reply)
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{}", line).unwrap();
+        }
+
+        fn receive(&mut self) -> Option<Value> {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line).unwrap() {
+                0 => None,
+                _ => Some(serde_json::from_str(&line).unwrap()),
+            }
+        }
+
+        /// Runs `line` and returns the messages it produced.
+        fn run(&mut self, line: &str) -> Vec<Value> {
+            self.send(line);
+            let mut messages = vec![];
+            loop {
+                let reply = self.receive().unwrap();
+                match reply["type"].as_str() {
+                    Some("done") => {
+                        assert_eq!(reply["command"], line);
+                        return messages;
+                    }
+                    _ => messages.push(reply),
+                }
+            }
+        }
+    }
+
+    /// Starts a server for a new console that keeps nothing on disk and is never drawn, so
+    /// its clients' commands are run by the threads waiting for them.
+    fn start_server(token: &str) -> Server {
+        let mut console = Console::in_directory(None);
+        console.cvars_mut().set("log_file", false).unwrap();
+        let sender = console.sender();
+        let console = Box::leak(Box::new(Mutex::new(console)));
+        Server::start(
+            console,
+            sender,
+            "127.0.0.1:0".parse().unwrap(),
+            token.into(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_wrong_token() {
+        let server = start_server("secret");
+        let (mut client, reply) = Client::connect(&server, "guess");
+        assert_eq!(reply["type"], "auth");
+        assert_eq!(reply["ok"], false);
+        assert_eq!(client.receive(), None);
+        server.join();
+    }
+
+    #[test]
+    fn runs_commands_and_streams_messages() {
+        let server = start_server("secret");
+        let (mut client, reply) = Client::connect(&server, "secret");
+        assert_eq!(reply, json!({ "type": "auth", "ok": true }));
+
+        let messages = client.run("echo hello; echo world");
+        let texts: Vec<_> = messages.iter().map(|m| m["text"].clone()).collect();
+        assert_eq!(texts, [json!("hello"), json!("world")]);
+        assert!(messages
+            .iter()
+            .all(|m| m["type"] == "message" && m["kind"] == "Info"));
+
+        let messages = client.run("does_not_exist");
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["kind"], "Error");
+
+        server.join();
+        assert_eq!(client.receive(), None);
+    }
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    #[test]
+    fn generates_random_tokens() {
+        let token = generate_token().unwrap();
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token().unwrap());
+    }
+}
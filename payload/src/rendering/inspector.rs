@@ -0,0 +1,383 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use egui::{Color32, CtxRef, Key, RichText, Sense, Ui};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use super::{memory_viewer, panel::Panel};
+use crate::{
+    console::{self, ArgType, CommandSignature, Console},
+    game::{
+        reflection::{self, FieldInfo, TypeInfo, TypeRef},
+        types::TYPES,
+        zrender::RENDER_MANAGER,
+    },
+    memory,
+};
+
+/// How long a value stays highlighted after it changes.
+const HIGHLIGHT_DURATION: Duration = Duration::from_secs(1);
+const CHANGED_COLOR: Color32 = Color32::from_rgb(245, 245, 67);
+/// The most bytes of a type from outside the schema that are shown.
+const MAX_OPAQUE_BYTES: usize = 16;
+
+#[derive(Clone, Copy)]
+struct Root {
+    ty: &'static TypeInfo,
+    address: usize,
+}
+
+lazy_static! {
+    /// What the `inspect` command asked the inspector to show, taken when it is next drawn.
+    static ref REQUESTED_ROOT: Mutex<Option<Root>> = Mutex::new(None);
+}
+
+/// The last value read from somewhere, and when it last changed.
+struct Tracked {
+    bytes: Vec<u8>,
+    changed: Option<Instant>,
+    /// The frame it was last read in, so that values no longer shown can be forgotten.
+    frame: u64,
+}
+
+/// Shows the fields of a game structure as a tree of live values, following pointers and
+/// expanding arrays, with values that just changed highlighted.
+pub struct Inspector {
+    root: Option<Root>,
+    type_name: &'static str,
+    address_input: String,
+    error: Option<String>,
+    values: HashMap<(usize, usize), Tracked>,
+    frame: u64,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            type_name: "ZRenderManager",
+            address_input: "RENDER_MANAGER".to_owned(),
+            error: None,
+            values: HashMap::new(),
+            frame: 0,
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui) {
+        if self.root.is_none() {
+            // Start with the render manager once it has been found.
+            let render_manager = unsafe { RENDER_MANAGER };
+            if let (Some(render_manager), Some(ty)) =
+                (render_manager, reflection::find_type("ZRenderManager"))
+            {
+                self.root = Some(Root {
+                    ty,
+                    address: render_manager as usize,
+                });
+            }
+        }
+
+        self.root_bar_ui(ui);
+        ui.separator();
+
+        let now = Instant::now();
+        match self.root {
+            Some(root) => {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.struct_ui(ui, root.ty, root.address, now);
+                });
+            }
+            None => {
+                ui.small("Pick a type and enter the address of one to inspect it.");
+            }
+        }
+
+        let frame = self.frame;
+        self.values.retain(|_, tracked| tracked.frame == frame);
+        self.frame += 1;
+    }
+
+    fn root_bar_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("inspector_type")
+                .selected_text(self.type_name)
+                .show_ui(ui, |ui| {
+                    for ty in TYPES {
+                        ui.selectable_value(&mut self.type_name, ty.name, ty.name);
+                    }
+                });
+
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.address_input)
+                    .hint_text("Address, such as RENDER_MANAGER")
+                    .desired_width(220.0),
+            );
+            let entered = response.lost_focus() && ui.input().key_pressed(Key::Enter);
+            if ui.button("Inspect").clicked() || entered {
+                let result = console::evaluate_address(&self.address_input);
+                match (result, reflection::find_type(self.type_name)) {
+                    (Ok(address), Some(ty)) => {
+                        self.root = Some(Root { ty, address });
+                        self.error = None;
+                    }
+                    (Err(e), _) => self.error = Some(e.to_string()),
+                    (_, None) => self.error = Some(format!("Unknown type `{}`.", self.type_name)),
+                }
+            }
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::from_rgb(241, 76, 76), error);
+        }
+        if let Some(root) = self.root {
+            ui.small(format!("{} at 0x{:X}", root.ty.name, root.address));
+        }
+    }
+
+    fn struct_ui(&mut self, ui: &mut Ui, ty: &TypeInfo, address: usize, now: Instant) {
+        if ty.fields.is_empty() {
+            ui.small("No fields are known.");
+        }
+        for field in ty.fields {
+            self.field_ui(ui, field, address.wrapping_add(field.offset), now);
+        }
+    }
+
+    fn field_ui(&mut self, ui: &mut Ui, field: &FieldInfo, address: usize, now: Instant) {
+        let mut label = RichText::new(field.name).monospace();
+        if field.doc.is_some() {
+            label = label.underline();
+        }
+        let response = self.value_ui(ui, label, &field.ty, address, now);
+        if let (Some(response), Some(doc)) = (response, field.doc) {
+            response.on_hover_text(doc);
+        }
+    }
+
+    /// Shows the value of type `ty` at `address`, returning the response of its label if
+    /// it is shown on one row.
+    fn value_ui(
+        &mut self,
+        ui: &mut Ui,
+        label: RichText,
+        ty: &TypeRef,
+        address: usize,
+        now: Instant,
+    ) -> Option<egui::Response> {
+        match ty {
+            TypeRef::Struct(name) => match reflection::find_type(name) {
+                Some(info) => {
+                    let header = format!("{}: {}", label.text(), name);
+                    egui::CollapsingHeader::new(header)
+                        .id_source((address, info.name, label.text()))
+                        .show(ui, |ui| self.struct_ui(ui, info, address, now));
+                    None
+                }
+                None => Some(self.row_ui(ui, label, ty, address, RichText::new("?").weak())),
+            },
+            TypeRef::Array(element, len) => {
+                let header = format!("{}: {}", label.text(), ty.name());
+                egui::CollapsingHeader::new(header)
+                    .id_source((address, ty.name(), label.text()))
+                    .show(ui, |ui| match element.size() {
+                        Some(size) => {
+                            for index in 0..*len {
+                                let label = RichText::new(format!("[{}]", index)).monospace();
+                                let address = address.wrapping_add(index * size);
+                                self.value_ui(ui, label, element, address, now);
+                            }
+                        }
+                        None => {
+                            ui.small("The size of the elements is not known.");
+                        }
+                    });
+                None
+            }
+            TypeRef::Pointer(pointee) => self.pointer_ui(ui, label, ty, pointee, address, now),
+            TypeRef::Primitive(primitive) => {
+                let value = self
+                    .read(address, primitive.size(), now)
+                    .map(|(bytes, changed)| highlight(primitive.format(&bytes), changed));
+                Some(self.row_ui(ui, label, ty, address, unreadable_or(value)))
+            }
+            TypeRef::Opaque { size, .. } => {
+                let len = (*size).min(MAX_OPAQUE_BYTES);
+                let value = self.read(address, len, now).map(|(bytes, changed)| {
+                    let mut text = bytes
+                        .iter()
+                        .map(|byte| format!("{:02X}", byte))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    if *size > len {
+                        text.push_str(" …");
+                    }
+                    highlight(text, changed)
+                });
+                Some(self.row_ui(ui, label, ty, address, unreadable_or(value)))
+            }
+            TypeRef::Void => Some(self.row_ui(ui, label, ty, address, RichText::new("void"))),
+        }
+    }
+
+    fn pointer_ui(
+        &mut self,
+        ui: &mut Ui,
+        label: RichText,
+        ty: &TypeRef,
+        pointee: &TypeRef,
+        address: usize,
+        now: Instant,
+    ) -> Option<egui::Response> {
+        let (bytes, changed) = match self.read(address, std::mem::size_of::<usize>(), now) {
+            Some(read) => read,
+            None => return Some(self.row_ui(ui, label, ty, address, unreadable_or(None))),
+        };
+        let pointer = usize::from_le_bytes(bytes.try_into().unwrap());
+        let value = highlight(format!("0x{:X}", pointer), changed);
+
+        let target = match pointee {
+            TypeRef::Struct(name) if pointer != 0 && memory::is_readable(pointer, 1) => {
+                reflection::find_type(name)
+            }
+            _ => None,
+        };
+        match target {
+            Some(info) => {
+                let header = format!("{}: {}* = 0x{:X}", label.text(), info.name, pointer);
+                let header = match changed {
+                    true => RichText::new(header).color(CHANGED_COLOR),
+                    false => RichText::new(header),
+                };
+                egui::CollapsingHeader::new(header)
+                    .id_source((address, info.name, label.text()))
+                    .show(ui, |ui| self.struct_ui(ui, info, pointer, now));
+                None
+            }
+            None => Some(self.row_ui(ui, label, ty, address, value)),
+        }
+    }
+
+    /// Shows a value on one row, which can be clicked to see its memory.
+    fn row_ui(
+        &mut self,
+        ui: &mut Ui,
+        label: RichText,
+        ty: &TypeRef,
+        address: usize,
+        value: RichText,
+    ) -> egui::Response {
+        ui.horizontal(|ui| {
+            let response = ui.label(label);
+            ui.label(RichText::new(ty.name()).weak());
+            let value = ui
+                .add(egui::Label::new(value.monospace()).sense(Sense::click()))
+                .on_hover_text(format!("At 0x{:X}. Click to view it in memory.", address));
+            if value.clicked() {
+                memory_viewer::request(address);
+            }
+            response
+        })
+        .inner
+    }
+
+    /// Reads `len` bytes at `address`, returning them and whether they changed recently.
+    fn read(&mut self, address: usize, len: usize, now: Instant) -> Option<(Vec<u8>, bool)> {
+        let mut bytes = vec![0; len];
+        memory::read(address, &mut bytes).ok()?;
+
+        let frame = self.frame;
+        let tracked = self
+            .values
+            .entry((address, len))
+            .or_insert_with(|| Tracked {
+                bytes: bytes.clone(),
+                changed: None,
+                frame,
+            });
+        if tracked.bytes != bytes {
+            tracked.bytes = bytes.clone();
+            tracked.changed = Some(now);
+        }
+        tracked.frame = frame;
+
+        let changed = tracked
+            .changed
+            .is_some_and(|changed| now.duration_since(changed) < HIGHLIGHT_DURATION);
+        Some((bytes, changed))
+    }
+}
+
+fn highlight(text: String, changed: bool) -> RichText {
+    match changed {
+        true => RichText::new(text).color(CHANGED_COLOR),
+        false => RichText::new(text),
+    }
+}
+
+fn unreadable_or(value: Option<RichText>) -> RichText {
+    value.unwrap_or_else(|| RichText::new("??").weak())
+}
+
+impl Panel for Inspector {
+    fn name(&self) -> &'static str {
+        "Inspector"
+    }
+
+    fn description(&self) -> &'static str {
+        "The fields of a game structure, such as the render manager, as live values."
+    }
+
+    fn show(&mut self, ctx: &CtxRef, open: &mut bool) {
+        egui::Window::new("Inspector")
+            .open(open)
+            .default_width(480.0)
+            .show(ctx, |ui| self.ui(ui));
+    }
+
+    fn take_open_request(&mut self) -> bool {
+        match REQUESTED_ROOT.lock().take() {
+            Some(root) => {
+                self.type_name = root.ty.name;
+                self.address_input = format!("0x{:X}", root.address);
+                self.root = Some(root);
+                self.error = None;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command(
+        CommandSignature::new("inspect", "Opens the inspector on a game structure.")
+            .arg(
+                "type",
+                ArgType::String,
+                "The type of the structure, such as `ZRenderManager`.",
+            )
+            .arg(
+                "address",
+                ArgType::Address,
+                "The address of the structure, such as `RENDER_MANAGER`.",
+            ),
+        |console, args| {
+            let name = args.string("type")?;
+            let ty = reflection::find_type(name).ok_or_else(|| {
+                let names: Vec<_> = TYPES.iter().map(|ty| ty.name).collect();
+                anyhow::anyhow!(
+                    "Unknown type `{}`. The types are: {}",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+            let address = args.address("address")?;
+            *REQUESTED_ROOT.lock() = Some(Root { ty, address });
+            console.push_back_info(format!("Inspecting the {} at 0x{:X}.", ty.name, address));
+            Ok(())
+        },
+    );
+}
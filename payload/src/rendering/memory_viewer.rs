@@ -0,0 +1,328 @@
+use egui::{CtxRef, Key, RichText, Ui};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use super::panel::Panel;
+use crate::{
+    console::{self, ArgType, CommandSignature, Console},
+    memory,
+};
+
+const BYTES_PER_ROW: usize = 16;
+/// How many bytes from the selection the interpretations can use, which bounds strings.
+const INTERPRETED_BYTES: usize = 128;
+
+lazy_static! {
+    /// An address the `mem` command asked the viewer to show, taken when it is next drawn.
+    static ref REQUESTED_ADDRESS: Mutex<Option<usize>> = Mutex::new(None);
+}
+
+/// Opens the memory viewer at `address` when the overlay is next drawn.
+pub fn request(address: usize) {
+    *REQUESTED_ADDRESS.lock() = Some(address);
+}
+
+/// A hex and ASCII dump of the game's memory, with the value at the selected byte read as
+/// each common type. Bytes in pages that cannot be read are shown as `??`.
+pub struct MemoryViewer {
+    address: usize,
+    address_input: String,
+    error: Option<String>,
+    rows: usize,
+    /// The address of the selected byte.
+    selected: Option<usize>,
+    /// Addresses gone to before the current one, for going back.
+    history: Vec<usize>,
+}
+
+impl MemoryViewer {
+    pub fn new() -> Self {
+        Self {
+            address: 0,
+            address_input: String::new(),
+            error: None,
+            rows: 16,
+            selected: None,
+            history: vec![],
+        }
+    }
+
+    fn go_to(&mut self, address: usize) {
+        if address != self.address {
+            self.history.push(self.address);
+        }
+        self.address = address;
+        self.address_input = format!("0x{:X}", address);
+        self.selected = Some(address);
+        self.error = None;
+    }
+
+    fn ui(&mut self, ui: &mut Ui) {
+        self.address_bar_ui(ui);
+        ui.separator();
+
+        let bytes = memory::read_partial(self.address, self.rows * BYTES_PER_ROW);
+        self.dump_ui(ui, &bytes);
+        ui.separator();
+
+        match self.selected {
+            Some(selected) => self.interpretation_ui(ui, selected),
+            None => {
+                ui.small("Click a byte to see the values that start at it.");
+            }
+        }
+    }
+
+    fn address_bar_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.address_input)
+                    .hint_text("Address, such as HITMAN3.exe+0x1000")
+                    .desired_width(280.0),
+            );
+            let entered = response.lost_focus() && ui.input().key_pressed(Key::Enter);
+            if ui.button("Go").clicked() || entered {
+                let result = console::evaluate_address(&self.address_input);
+                match result {
+                    Ok(address) => self.go_to(address),
+                    Err(e) => self.error = Some(e.to_string()),
+                }
+            }
+
+            if ui
+                .add_enabled(!self.history.is_empty(), egui::Button::new("Back"))
+                .clicked()
+            {
+                if let Some(address) = self.history.pop() {
+                    self.address = address;
+                    self.address_input = format!("0x{:X}", address);
+                    self.selected = None;
+                }
+            }
+
+            let page = self.rows * BYTES_PER_ROW;
+            if ui.button("⏶").on_hover_text("Previous page").clicked() {
+                self.address = self.address.saturating_sub(page);
+            }
+            if ui.button("⏷").on_hover_text("Next page").clicked() {
+                self.address = self.address.saturating_add(page);
+            }
+
+            ui.add(egui::DragValue::new(&mut self.rows).clamp_range(1..=64));
+            ui.label("rows");
+        });
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::from_rgb(241, 76, 76), error);
+        }
+    }
+
+    fn dump_ui(&mut self, ui: &mut Ui, bytes: &[Option<u8>]) {
+        egui::Grid::new("memory_dump")
+            .spacing(egui::vec2(4.0, 2.0))
+            .show(ui, |ui| {
+                for (row, chunk) in bytes.chunks(BYTES_PER_ROW).enumerate() {
+                    let row_address = self.address.wrapping_add(row * BYTES_PER_ROW);
+                    ui.label(
+                        RichText::new(format!("{:016X}", row_address))
+                            .monospace()
+                            .weak(),
+                    );
+
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().item_spacing.x = 4.0;
+                        for (column, byte) in chunk.iter().enumerate() {
+                            let address = row_address.wrapping_add(column);
+                            let text = match byte {
+                                Some(byte) => format!("{:02X}", byte),
+                                None => "??".to_owned(),
+                            };
+                            let label = egui::SelectableLabel::new(
+                                self.selected == Some(address),
+                                RichText::new(text).monospace(),
+                            );
+                            if ui.add(label).clicked() {
+                                self.selected = Some(address);
+                            }
+                        }
+                    });
+
+                    let ascii: String = chunk
+                        .iter()
+                        .map(|byte| match byte {
+                            Some(byte) if byte.is_ascii_graphic() || *byte == b' ' => *byte as char,
+                            Some(_) => '.',
+                            None => '?',
+                        })
+                        .collect();
+                    ui.label(RichText::new(ascii).monospace());
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn interpretation_ui(&mut self, ui: &mut Ui, selected: usize) {
+        let bytes = memory::read_partial(selected, INTERPRETED_BYTES);
+        let mut follow = None;
+
+        egui::Grid::new("memory_interpretation")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new(format!("At 0x{:X}", selected)).strong());
+                ui.end_row();
+
+                for (name, value) in interpretations(&bytes) {
+                    ui.label(name);
+                    ui.label(RichText::new(value.unwrap_or_else(|| "??".to_owned())).monospace());
+                    ui.end_row();
+                }
+
+                ui.label("pointer");
+                match read_array::<8>(&bytes).map(u64::from_le_bytes) {
+                    Some(pointer) => {
+                        let pointer = pointer as usize;
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(format!("0x{:X}", pointer)).monospace());
+                            let readable = memory::is_readable(pointer, 1);
+                            let response = ui
+                                .add_enabled(readable, egui::Button::new("Follow"))
+                                .on_disabled_hover_text("The pointer is not to readable memory.");
+                            if response.clicked() {
+                                follow = Some(pointer);
+                            }
+                        });
+                    }
+                    None => {
+                        ui.label(RichText::new("??").monospace());
+                    }
+                }
+                ui.end_row();
+            });
+
+        if let Some(pointer) = follow {
+            self.go_to(pointer);
+        }
+    }
+}
+
+impl Panel for MemoryViewer {
+    fn name(&self) -> &'static str {
+        "Memory"
+    }
+
+    fn description(&self) -> &'static str {
+        "A hex and ASCII view of the game's memory."
+    }
+
+    fn show(&mut self, ctx: &CtxRef, open: &mut bool) {
+        egui::Window::new("Memory")
+            .open(open)
+            .default_width(720.0)
+            .show(ctx, |ui| self.ui(ui));
+    }
+
+    fn take_open_request(&mut self) -> bool {
+        match REQUESTED_ADDRESS.lock().take() {
+            Some(address) => {
+                self.go_to(address);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns the first `N` bytes, if they could all be read.
+fn read_array<const N: usize>(bytes: &[Option<u8>]) -> Option<[u8; N]> {
+    let mut array = [0; N];
+    for (value, byte) in array.iter_mut().zip(bytes.get(..N)?) {
+        *value = (*byte)?;
+    }
+    Some(array)
+}
+
+/// Reads the start of `bytes` as each integer, float and string type, with `None` for the
+/// types whose bytes could not all be read.
+fn interpretations(bytes: &[Option<u8>]) -> Vec<(&'static str, Option<String>)> {
+    let utf8 = {
+        let bytes: Vec<u8> = bytes.iter().map_while(|byte| *byte).collect();
+        let end = bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(bytes.len());
+        format!("{:?}", String::from_utf8_lossy(&bytes[..end]))
+    };
+    let utf16 = {
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map_while(|pair| match pair {
+                [Some(low), Some(high)] => Some(u16::from_le_bytes([*low, *high])),
+                _ => None,
+            })
+            .take_while(|unit| *unit != 0)
+            .collect();
+        format!("{:?}", String::from_utf16_lossy(&units))
+    };
+    let is_string_readable = bytes.first().is_some_and(Option::is_some);
+
+    vec![
+        (
+            "u8",
+            read_array(bytes).map(|b| u8::from_le_bytes(b).to_string()),
+        ),
+        (
+            "i8",
+            read_array(bytes).map(|b| i8::from_le_bytes(b).to_string()),
+        ),
+        (
+            "u16",
+            read_array(bytes).map(|b| u16::from_le_bytes(b).to_string()),
+        ),
+        (
+            "i16",
+            read_array(bytes).map(|b| i16::from_le_bytes(b).to_string()),
+        ),
+        (
+            "u32",
+            read_array(bytes).map(|b| u32::from_le_bytes(b).to_string()),
+        ),
+        (
+            "i32",
+            read_array(bytes).map(|b| i32::from_le_bytes(b).to_string()),
+        ),
+        (
+            "u64",
+            read_array(bytes).map(|b| u64::from_le_bytes(b).to_string()),
+        ),
+        (
+            "i64",
+            read_array(bytes).map(|b| i64::from_le_bytes(b).to_string()),
+        ),
+        (
+            "f32",
+            read_array(bytes).map(|b| f32::from_le_bytes(b).to_string()),
+        ),
+        (
+            "f64",
+            read_array(bytes).map(|b| f64::from_le_bytes(b).to_string()),
+        ),
+        ("UTF-8", is_string_readable.then_some(utf8)),
+        ("UTF-16", is_string_readable.then_some(utf16)),
+    ]
+}
+
+pub fn register_commands(console: &mut Console) {
+    console.register_command(
+        CommandSignature::new("mem", "Opens the memory viewer at an address.").arg(
+            "address",
+            ArgType::Address,
+            "The address to show, such as `HITMAN3.exe+0x1A2B3C` or `[RENDER_MANAGER]+0x10`.",
+        ),
+        |console, args| {
+            let address = args.address("address")?;
+            request(address);
+            console.push_back_info(format!("Showing 0x{:X} in the memory viewer.", address));
+            Ok(())
+        },
+    );
+}
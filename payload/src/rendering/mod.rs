@@ -1,4 +1,8 @@
+pub mod inspector;
+pub mod memory_viewer;
 pub mod overlay;
+pub mod palette;
+pub mod panel;
 
 use crate::{detouring::prelude::*, game::zrender::RENDER_MANAGER};
 use anyhow::Result;
@@ -165,10 +169,13 @@ fn resize_buffers(
     newformat: DXGI_FORMAT,
     swapchainflags: u32,
 ) -> HRESULT {
-    #[cfg(feature = "debug-logging")]
-    println!(
+    log::debug!(
         "resize_buffers(buffercount: {}, width: {}, height: {}, newformat: {}, swapchainflags: {})",
-        buffercount, width, height, newformat.0, swapchainflags
+        buffercount,
+        width,
+        height,
+        newformat.0,
+        swapchainflags
     );
     OVERLAY.lock().unwrap().resize(&|| {
         RESIZE_BUFFERS_DETOUR.call(
@@ -183,8 +190,7 @@ fn resize_buffers(
 }
 
 fn resize_target(this: IDXGISwapChain, pnewtargetparameters: *const DXGI_MODE_DESC) -> HRESULT {
-    #[cfg(feature = "debug-logging")]
-    println!(
+    log::debug!(
         "resize_target(pnewtargetparameters: 0x{:X})",
         pnewtargetparameters as usize
     );
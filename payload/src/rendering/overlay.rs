@@ -15,12 +15,22 @@ use windows::Win32::{
     },
 };
 
+use super::{
+    inspector::Inspector,
+    memory_viewer::MemoryViewer,
+    palette::{Entry, Palette},
+    panel::{ConsolePanel, Panel},
+};
+
 pub struct Overlay {
     ctx: CtxRef,
     input: Option<WindowInput>,
     capture: bool,
     painter: Option<PainterDX12>,
     render: bool,
+    /// Every panel, and whether it is open.
+    panels: Vec<(Box<dyn Panel>, bool)>,
+    palette: Palette,
 }
 
 impl Overlay {
@@ -31,9 +41,19 @@ impl Overlay {
             capture: false,
             painter: None,
             render: true,
+            panels: vec![
+                (Box::new(ConsolePanel), true),
+                (Box::new(MemoryViewer::new()), false),
+                (Box::new(Inspector::new()), false),
+            ],
+            palette: Palette::new(),
         }
     }
 
+    pub fn add_panel(&mut self, panel: impl Panel + 'static, open: bool) {
+        self.panels.push((Box::new(panel), open));
+    }
+
     pub fn wnd_proc(&mut self, hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> bool {
         if self.input.is_none() {
             self.input = Some(WindowInput::new(hwnd));
@@ -52,6 +72,11 @@ impl Overlay {
         }
     }
 
+    /// Whether key presses are going to the overlay rather than the game.
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.capture && self.ctx.wants_keyboard_input()
+    }
+
     pub fn resize<F, R>(&mut self, callback: F) -> R
     where
         F: FnOnce() -> R,
@@ -69,6 +94,8 @@ impl Overlay {
         command_queue: &ID3D12CommandQueue,
         swap_chain: &IDXGISwapChain4,
     ) {
+        crate::CONSOLE.lock().unwrap().process_events();
+
         if self.painter.is_none() {
             self.painter = Some(
                 PainterDX12::new(device.clone(), command_queue.clone(), swap_chain.clone())
@@ -80,17 +107,36 @@ impl Overlay {
             (self.render, &mut self.input, &mut self.painter)
         {
             let ctx = &mut self.ctx;
-            let input = input.get_input();
+            let raw_input = input.get_input();
 
-            let (_, shapes) = ctx.run(input, |ctx| {
+            let (output, shapes) = ctx.run(raw_input, |ctx| {
                 Self::show_title_bar(ctx, self.capture);
 
                 if self.capture {
-                    let mut console = crate::CONSOLE.lock().unwrap();
-                    console.show(ctx);
+                    if ctx.input().modifiers.ctrl && ctx.input().key_pressed(egui::Key::P) {
+                        self.palette.toggle();
+                    }
+
+                    for (panel, open) in &mut self.panels {
+                        *open |= panel.take_open_request();
+                        if *open {
+                            panel.show(ctx, open);
+                        }
+                    }
+
+                    if self.palette.is_open() {
+                        let entries = Self::palette_entries(&self.panels);
+                        if let Some(entry) = self.palette.show(ctx, entries) {
+                            Self::activate(&mut self.panels, entry);
+                        }
+                    }
                 }
             });
 
+            if let Err(e) = input.handle_output(&output) {
+                log::error!("Failed to handle egui output: {}", e);
+            }
+
             painter.upload_egui_texture(&ctx.font_image());
             painter
                 .paint_meshes(ctx.tessellate(shapes), ctx.pixels_per_point())
@@ -98,6 +144,50 @@ impl Overlay {
         }
     }
 
+    fn palette_entries(panels: &[(Box<dyn Panel>, bool)]) -> Vec<Entry> {
+        let commands = crate::CONSOLE.lock().unwrap().command_summaries();
+        let panels = panels
+            .iter()
+            .enumerate()
+            .map(|(index, (panel, open))| Entry::Panel {
+                index,
+                name: panel.name(),
+                description: panel.description(),
+                open: *open,
+            });
+        commands
+            .into_iter()
+            .map(Entry::Command)
+            .chain(panels)
+            .collect()
+    }
+
+    /// Toggles a panel picked from the palette, or runs a command picked from it. Commands
+    /// that need arguments are put on the console's command line to be finished instead.
+    fn activate(panels: &mut [(Box<dyn Panel>, bool)], entry: Entry) {
+        let command = match entry {
+            Entry::Panel { index, .. } => {
+                panels[index].1 ^= true;
+                return;
+            }
+            Entry::Command(command) => command,
+        };
+
+        if let Some((_, open)) = panels
+            .iter_mut()
+            .find(|(panel, _)| panel.name() == ConsolePanel.name())
+        {
+            *open = true;
+        }
+
+        let mut console = crate::CONSOLE.lock().unwrap();
+        if command.requires_arguments {
+            console.set_input(format!("{} ", command.name));
+        } else {
+            console.submit(&command.name);
+        }
+    }
+
     fn show_title_bar(ctx: &CtxRef, capture: bool) {
         let frame = egui::Frame::none();
         egui::TopBottomPanel::top("title_bar")
@@ -113,6 +203,9 @@ impl Overlay {
                             false => "open",
                         }
                     ));
+                    if capture {
+                        ui.small("Press Ctrl+P to search commands and panels");
+                    }
                 })
             });
     }
@@ -0,0 +1,211 @@
+use egui::{CtxRef, Key};
+
+use crate::console::CommandSummary;
+
+const MAX_SHOWN_ENTRIES: usize = 12;
+
+pub enum Entry {
+    Command(CommandSummary),
+    Panel {
+        index: usize,
+        name: &'static str,
+        description: &'static str,
+        open: bool,
+    },
+}
+
+impl Entry {
+    fn name(&self) -> &str {
+        match self {
+            Entry::Command(command) => &command.name,
+            Entry::Panel { name, .. } => name,
+        }
+    }
+}
+
+/// Scores how well `pattern` matches `text` as a case-insensitive subsequence, favouring
+/// runs of consecutive characters and the starts of words. Returns `None` if `text` does
+/// not contain every character of `pattern` in order.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let mut pattern = pattern.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+
+    for c in text.chars() {
+        let wanted = match pattern.peek() {
+            Some(wanted) => *wanted,
+            None => break,
+        };
+
+        if c.to_lowercase().eq(std::iter::once(wanted)) {
+            let word_start = previous.is_none_or(|p| !p.is_alphanumeric())
+                || (c.is_uppercase() && previous.is_some_and(char::is_lowercase));
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if word_start {
+                score += 3;
+            }
+            pattern.next();
+            previous_matched = true;
+        } else {
+            score -= 1;
+            previous_matched = false;
+        }
+        previous = Some(c);
+    }
+
+    pattern.peek().is_none().then_some(score)
+}
+
+/// A Ctrl+P style popup for finding and running commands and opening panels.
+pub struct Palette {
+    open: bool,
+    query: String,
+    selected: usize,
+    focus: bool,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            query: String::new(),
+            selected: 0,
+            focus: false,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+        self.focus = self.open;
+    }
+
+    /// Draws the palette, returning the entry that was picked, if any.
+    pub fn show(&mut self, ctx: &CtxRef, entries: Vec<Entry>) -> Option<Entry> {
+        let mut matches: Vec<_> = entries
+            .into_iter()
+            .filter_map(|entry| Some((fuzzy_score(&self.query, entry.name())?, entry)))
+            .collect();
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(a.name().len().cmp(&b.name().len()))
+                .then(a.name().cmp(b.name()))
+        });
+        matches.truncate(MAX_SHOWN_ENTRIES);
+
+        {
+            let input = ctx.input();
+            if input.key_pressed(Key::Escape) {
+                self.open = false;
+            }
+            if input.key_pressed(Key::ArrowDown) {
+                self.selected += 1;
+            }
+            if input.key_pressed(Key::ArrowUp) {
+                self.selected = self.selected.saturating_sub(1);
+            }
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut picked = None;
+        egui::Area::new("command_palette")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(500.0);
+
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.query)
+                            .hint_text("Search commands and panels")
+                            .desired_width(f32::INFINITY),
+                    );
+                    if std::mem::take(&mut self.focus) {
+                        response.request_focus();
+                    }
+                    if response.changed() {
+                        self.selected = 0;
+                    }
+                    if response.lost_focus() && ui.input().key_pressed(Key::Enter) {
+                        picked = Some(self.selected);
+                    }
+                    ui.separator();
+
+                    if matches.is_empty() {
+                        ui.small("Nothing matches.");
+                    }
+                    for (index, (_, entry)) in matches.iter().enumerate() {
+                        let (label, description) = match entry {
+                            Entry::Command(command) => {
+                                (command.usage.clone(), command.description.as_str())
+                            }
+                            Entry::Panel {
+                                name,
+                                description,
+                                open,
+                                ..
+                            } => (
+                                format!("{} {}", if *open { "Close" } else { "Open" }, name),
+                                *description,
+                            ),
+                        };
+
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(index == self.selected, label).clicked() {
+                                picked = Some(index);
+                            }
+                            ui.small(description);
+                        });
+                    }
+                });
+            });
+
+        let picked = picked.filter(|index| *index < matches.len())?;
+        self.open = false;
+        Some(matches.swap_remove(picked).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_rank_above_subsequences() {
+        let prefix = fuzzy_score("rev", "revert-all").unwrap();
+        let subsequence = fuzzy_score("rev", "reload_vertex").unwrap();
+        assert!(prefix > subsequence);
+        assert!(fuzzy_score("rev", "rev").unwrap() >= prefix);
+    }
+
+    #[test]
+    fn favours_starts_of_words() {
+        let plain = fuzzy_score("ma", "memoryaddress").unwrap();
+        assert!(fuzzy_score("ma", "memory_address").unwrap() > plain);
+        assert!(fuzzy_score("ma", "memoryAddress").unwrap() > plain);
+    }
+
+    #[test]
+    fn ignores_case() {
+        let lower = fuzzy_score("mem", "memory");
+        assert_eq!(fuzzy_score("MEM", "memory"), lower);
+        assert_eq!(fuzzy_score("mem", "Memory"), lower);
+    }
+
+    #[test]
+    fn non_matches_have_no_score() {
+        assert_eq!(fuzzy_score("xyz", "memory"), None);
+        assert_eq!(fuzzy_score("yrom", "memory"), None);
+        assert_eq!(fuzzy_score("memoryy", "memory"), None);
+        assert_eq!(fuzzy_score("", "memory"), Some(0));
+    }
+}
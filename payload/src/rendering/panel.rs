@@ -0,0 +1,30 @@
+use egui::CtxRef;
+
+/// A part of the overlay that can be opened and closed, such as from the command palette.
+pub trait Panel: Send {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    /// Draws the panel. Panels with a close button should clear `open` when it is pressed.
+    fn show(&mut self, ctx: &CtxRef, open: &mut bool);
+    /// Returns whether something outside the overlay, such as a command, asked for the panel
+    /// to be opened since this was last called.
+    fn take_open_request(&mut self) -> bool {
+        false
+    }
+}
+
+pub struct ConsolePanel;
+
+impl Panel for ConsolePanel {
+    fn name(&self) -> &'static str {
+        "Console"
+    }
+
+    fn description(&self) -> &'static str {
+        "The command line and its output."
+    }
+
+    fn show(&mut self, ctx: &CtxRef, _open: &mut bool) {
+        crate::CONSOLE.lock().unwrap().show(ctx);
+    }
+}
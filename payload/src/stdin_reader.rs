@@ -0,0 +1,78 @@
+use std::{
+    io::{self, BufRead},
+    os::windows::io::AsRawHandle,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use windows::Win32::{Foundation::HANDLE, System::IO::CancelSynchronousIo};
+
+use crate::{MessageType, CONSOLE, CONSOLE_SENDER};
+
+/// Runs the command lines typed into the debug console, printing the messages they produce.
+pub struct StdinReader {
+    thread: JoinHandle<()>,
+    stopping: Arc<AtomicBool>,
+}
+
+impl StdinReader {
+    pub fn spawn() -> Self {
+        let stopping = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stopping = stopping.clone();
+            move || read_lines(&stopping)
+        });
+        Self { thread, stopping }
+    }
+
+    /// Interrupts the pending read and waits for the thread to finish, so that it is not
+    /// left running in the payload once it is unloaded.
+    pub fn stop(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        unsafe {
+            CancelSynchronousIo(HANDLE(self.thread.as_raw_handle() as isize));
+        }
+        let _ = self.thread.join();
+    }
+}
+
+fn read_lines(stopping: &AtomicBool) {
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) if !stopping.load(Ordering::SeqCst) => line,
+            _ => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // The command runs with everything else sent to the console, rather than on this
+        // thread while the overlay may be drawing it.
+        let result = CONSOLE_SENDER.run_and_wait(&CONSOLE, move |console| {
+            let count = console.message_count();
+            console.submit(&line);
+            let messages: Vec<(bool, String)> = console
+                .messages_since(count)
+                .filter(|message| message.message_type() != &MessageType::Command)
+                .map(|message| {
+                    let is_error = message.message_type() == &MessageType::Error;
+                    (is_error, message.text().to_owned())
+                })
+                .collect();
+            messages
+        });
+        let messages = match result {
+            Ok(messages) => messages,
+            Err(_) => return,
+        };
+        for (is_error, text) in messages {
+            match is_error {
+                true => eprintln!("{}", text),
+                false => println!("{}", text),
+            }
+        }
+    }
+}